@@ -0,0 +1,96 @@
+//! Compares drawing rectangles as indexed vertices, expanded on the CPU,
+//! with drawing them as instances expanded by the vertex shader.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use ruby::{Color, CornerRadii, DrawContext, Rect, RoundedRect, State};
+
+const COUNTS: [usize; 3] = [100, 1_000, 10_000];
+
+/// The size of the render target, in logical pixels.
+const SIZE: u32 = 512;
+
+/// Lays out `count` small rectangles in a grid covering the target.
+fn rects(count: usize) -> impl Iterator<Item = Rect> {
+    let columns = (count as f32).sqrt().ceil() as usize;
+    let size = SIZE as f32 / columns as f32;
+    (0..count).map(move |i| {
+        let x = (i % columns) as f32 * size;
+        let y = (i / columns) as f32 * size;
+        Rect::new(x, y, size * 0.8, size * 0.8)
+    })
+}
+
+fn draw_vertices(ctx: &mut DrawContext, count: usize) {
+    for rect in rects(count) {
+        ctx.quad(rect, Color::BLUE);
+    }
+}
+
+fn draw_instances(ctx: &mut DrawContext, count: usize) {
+    for rect in rects(count) {
+        ctx.rect(&RoundedRect::new(rect, CornerRadii::all(0.0), Color::BLUE));
+    }
+}
+
+/// The cost of recording the rectangles on the CPU.
+fn record(c: &mut Criterion) {
+    let mut group = c.benchmark_group("record");
+    for count in COUNTS {
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("vertices", count), &count, |b, &count| {
+            b.iter(|| {
+                let mut ctx = DrawContext::new();
+                draw_vertices(&mut ctx, count);
+                ctx
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("instances", count), &count, |b, &count| {
+            b.iter(|| {
+                let mut ctx = DrawContext::new();
+                draw_instances(&mut ctx, count);
+                ctx
+            })
+        });
+    }
+    group.finish();
+}
+
+/// The cost of a whole frame, from recording to the frame being read
+/// back, which waits for the GPU to finish.
+fn frame(c: &mut Criterion) {
+    let mut state = match smol::block_on(State::headless(SIZE, SIZE)) {
+        Ok(state) => state,
+        Err(err) => {
+            eprintln!("skipping frame benchmarks, no adapter: {err}");
+            return;
+        }
+    };
+
+    let mut group = c.benchmark_group("frame");
+    group.sample_size(20);
+    for count in COUNTS {
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("vertices", count), &count, |b, &count| {
+            b.iter(|| {
+                let mut ctx = DrawContext::new();
+                draw_vertices(&mut ctx, count);
+                state.draw_context(&mut ctx);
+                state.render().unwrap();
+                state.capture()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("instances", count), &count, |b, &count| {
+            b.iter(|| {
+                let mut ctx = DrawContext::new();
+                draw_instances(&mut ctx, count);
+                state.draw_context(&mut ctx);
+                state.render().unwrap();
+                state.capture()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, record, frame);
+criterion_main!(benches);
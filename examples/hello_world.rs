@@ -2,6 +2,9 @@ use ruby::App;
 
 
 
-fn main(){
-	App::new().run();
+fn main() -> Result<(), ruby::Error>{
+	App::new()
+		.title("Hello world")
+		.size(800.0, 600.0)
+		.run()
 }
\ No newline at end of file
@@ -0,0 +1,605 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy},
+    window::{Fullscreen, Icon, WindowAttributes, WindowId},
+};
+
+use crate::{Constraints, DrawContext, Error, Fonts, Position, Size, State, Widget, WidgetNode};
+
+/// The configuration used to create a window.
+#[derive(Default)]
+pub struct WindowConfig {
+    attributes: WindowAttributes,
+    /// The window icon, which is decoded when the window is created.
+    icon: Option<IconSource>,
+    /// The root of the window's widget tree.
+    root: Option<Box<dyn Widget + Send>>,
+    /// The fonts the widget tree is measured with, or `None` for
+    /// the default fonts.
+    fonts: Option<Fonts>,
+}
+
+impl WindowConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the title of the window.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.attributes.title = title.into();
+        self
+    }
+
+    /// Sets the initial inner size of the window in logical pixels.
+    pub fn size(mut self, width: f64, height: f64) -> Self {
+        self.attributes.inner_size = Some(winit::dpi::LogicalSize::new(width, height).into());
+        self
+    }
+
+    /// Sets the minimum inner size of the window in logical pixels.
+    pub fn min_size(mut self, width: f64, height: f64) -> Self {
+        self.attributes.min_inner_size = Some(winit::dpi::LogicalSize::new(width, height).into());
+        self
+    }
+
+    /// Sets the maximum inner size of the window in logical pixels.
+    pub fn max_size(mut self, width: f64, height: f64) -> Self {
+        self.attributes.max_inner_size = Some(winit::dpi::LogicalSize::new(width, height).into());
+        self
+    }
+
+    /// Sets whether the window can be resized by the user, defaults to `true`.
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.attributes.resizable = resizable;
+        self
+    }
+
+    /// Sets whether the window has a title bar and borders, defaults to `true`.
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.attributes.decorations = decorations;
+        self
+    }
+
+    /// Sets whether the window background is transparent, defaults to `false`.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.attributes.transparent = transparent;
+        self
+    }
+
+    /// Sets whether the window starts maximized, defaults to `false`.
+    pub fn maximized(mut self, maximized: bool) -> Self {
+        self.attributes.maximized = maximized;
+        self
+    }
+
+    /// Sets whether the window starts in borderless fullscreen on the
+    /// current monitor, defaults to `false`.
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.attributes.fullscreen = fullscreen.then_some(Fullscreen::Borderless(None));
+        self
+    }
+
+    /// Sets the window icon from an image file.
+    ///
+    /// The image is loaded when the window is created, any format
+    /// supported by the `image` crate can be used.
+    pub fn icon(mut self, path: impl Into<PathBuf>) -> Self {
+        self.icon = Some(IconSource::Path(path.into()));
+        self
+    }
+
+    /// Sets the window icon from the bytes of an encoded image, such
+    /// as a PNG file included with [`include_bytes`].
+    pub fn icon_from_bytes(mut self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.icon = Some(IconSource::Bytes(bytes.into()));
+        self
+    }
+
+    /// Sets the root widget, which is laid out to fit the window.
+    pub fn root(mut self, widget: impl Widget + Send + 'static) -> Self {
+        self.root = Some(Box::new(widget));
+        self
+    }
+
+    /// Sets the fonts that widgets in the window measure and draw their
+    /// text with, instead of the bundled DejaVu Sans.
+    pub fn fonts(mut self, fonts: Fonts) -> Self {
+        self.fonts = Some(fonts);
+        self
+    }
+}
+
+impl fmt::Debug for WindowConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowConfig")
+            .field("attributes", &self.attributes)
+            .field("icon", &self.icon)
+            .field("root", &self.root.is_some())
+            .field("fonts", &self.fonts)
+            .finish()
+    }
+}
+
+/// Where to load a window icon from.
+#[derive(Debug, Clone)]
+enum IconSource {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl IconSource {
+    /// Decodes the image into a window [`Icon`].
+    fn load(&self) -> Result<Icon, Error> {
+        let image = match self {
+            Self::Path(path) => image::open(path),
+            Self::Bytes(bytes) => image::load_from_memory(bytes),
+        }
+        .map_err(Error::LoadIcon)?;
+        let image = image.into_rgba8();
+        let (width, height) = image.dimensions();
+
+        Ok(Icon::from_rgba(image.into_raw(), width, height)?)
+    }
+}
+
+/// An open window with its renderer state and widget tree.
+struct AppWindow {
+    state: State,
+    root: Option<WidgetNode>,
+}
+
+impl AppWindow {
+    /// Lays out and draws the widget tree, then renders the frame.
+    fn render(&mut self) -> Result<(), Error> {
+        // Windows with a widget tree always have fonts, see `App::open_window`
+        if let (Some(root), Some(fonts)) = (&mut self.root, self.state.fonts()) {
+            let size = self.state.logical_size();
+            let constraints = Constraints::loose(Size::new(size.width, size.height));
+            root.layout(constraints, fonts);
+
+            let mut ctx = DrawContext::with_scale_factor(self.state.scale_factor() as f32);
+            root.draw(Position::ZERO, &mut ctx);
+            self.state.draw_context(&mut ctx);
+        }
+
+        self.state.render()
+    }
+}
+
+impl fmt::Debug for AppWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AppWindow")
+            .field("state", &self.state)
+            .field("root", &self.root.is_some())
+            .finish()
+    }
+}
+
+/// A request sent to the app through an [`AppHandle`].
+#[derive(Debug)]
+enum Command {
+    OpenWindow(Box<WindowConfig>),
+    CloseWindow(WindowId),
+    Exit,
+}
+
+/// State shared between the app and its handles.
+#[derive(Debug, Default)]
+struct Shared {
+    commands: Mutex<Vec<Command>>,
+    windows: Mutex<Vec<WindowId>>,
+    /// Wakes up the event loop, set once the app is running.
+    proxy: Mutex<Option<EventLoopProxy<()>>>,
+}
+
+/// A handle used to control an [`App`] while it's running.
+///
+/// Handles can be cloned and sent to other threads. Requests made
+/// before the app starts running are handled once it starts.
+#[derive(Debug, Clone)]
+pub struct AppHandle {
+    shared: Arc<Shared>,
+}
+
+impl AppHandle {
+    fn send(&self, command: Command) {
+        self.shared.commands.lock().unwrap().push(command);
+        if let Some(proxy) = self.shared.proxy.lock().unwrap().as_ref() {
+            // The event loop has already exited if this fails
+            let _ = proxy.send_event(());
+        }
+    }
+
+    /// Opens a new window.
+    pub fn open_window(&self, config: WindowConfig) {
+        self.send(Command::OpenWindow(Box::new(config)));
+    }
+
+    /// Closes the window with the given `id`.
+    pub fn close_window(&self, id: WindowId) {
+        self.send(Command::CloseWindow(id));
+    }
+
+    /// Closes all the windows and exits the app.
+    pub fn exit(&self) {
+        self.send(Command::Exit);
+    }
+
+    /// The ids of the windows that are currently open.
+    pub fn windows(&self) -> Vec<WindowId> {
+        self.shared.windows.lock().unwrap().clone()
+    }
+}
+
+#[derive(Debug)]
+pub struct App {
+    /// The configuration of the main window, which is taken
+    /// when the app is first resumed.
+    main_window: Option<WindowConfig>,
+    main_window_id: Option<WindowId>,
+    windows: HashMap<WindowId, AppWindow>,
+    /// Only exit once every window has been closed, instead of
+    /// when the main window is closed.
+    exit_on_last_window: bool,
+    shared: Arc<Shared>,
+    /// An error that caused the event loop to exit.
+    error: Option<Error>,
+}
+
+impl App {
+    /// Creates an app that opens a main window with the default
+    /// configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the main window configuration.
+    fn map_main_window(mut self, f: impl FnOnce(WindowConfig) -> WindowConfig) -> Self {
+        self.main_window = self.main_window.map(f);
+        self
+    }
+
+    /// Sets the title of the main window.
+    pub fn title(self, title: impl Into<String>) -> Self {
+        self.map_main_window(|config| config.title(title))
+    }
+
+    /// Sets the initial inner size of the main window in logical pixels.
+    pub fn size(self, width: f64, height: f64) -> Self {
+        self.map_main_window(|config| config.size(width, height))
+    }
+
+    /// Sets the minimum inner size of the main window in logical pixels.
+    pub fn min_size(self, width: f64, height: f64) -> Self {
+        self.map_main_window(|config| config.min_size(width, height))
+    }
+
+    /// Sets the maximum inner size of the main window in logical pixels.
+    pub fn max_size(self, width: f64, height: f64) -> Self {
+        self.map_main_window(|config| config.max_size(width, height))
+    }
+
+    /// Sets whether the main window can be resized by the user, defaults to `true`.
+    pub fn resizable(self, resizable: bool) -> Self {
+        self.map_main_window(|config| config.resizable(resizable))
+    }
+
+    /// Sets whether the main window has a title bar and borders, defaults to `true`.
+    pub fn decorations(self, decorations: bool) -> Self {
+        self.map_main_window(|config| config.decorations(decorations))
+    }
+
+    /// Sets whether the main window background is transparent, defaults to `false`.
+    pub fn transparent(self, transparent: bool) -> Self {
+        self.map_main_window(|config| config.transparent(transparent))
+    }
+
+    /// Sets whether the main window starts maximized, defaults to `false`.
+    pub fn maximized(self, maximized: bool) -> Self {
+        self.map_main_window(|config| config.maximized(maximized))
+    }
+
+    /// Sets whether the main window starts in borderless fullscreen on the
+    /// current monitor, defaults to `false`.
+    pub fn fullscreen(self, fullscreen: bool) -> Self {
+        self.map_main_window(|config| config.fullscreen(fullscreen))
+    }
+
+    /// Sets the main window icon from an image file.
+    ///
+    /// The image is loaded when the window is created, any format
+    /// supported by the `image` crate can be used.
+    pub fn icon(self, path: impl Into<PathBuf>) -> Self {
+        self.map_main_window(|config| config.icon(path))
+    }
+
+    /// Sets the main window icon from the bytes of an encoded image, such
+    /// as a PNG file included with [`include_bytes`].
+    pub fn icon_from_bytes(self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.map_main_window(|config| config.icon_from_bytes(bytes))
+    }
+
+    /// Sets the root widget of the main window.
+    pub fn root(self, widget: impl Widget + Send + 'static) -> Self {
+        self.map_main_window(|config| config.root(widget))
+    }
+
+    /// Sets the fonts of the main window.
+    pub fn fonts(self, fonts: Fonts) -> Self {
+        self.map_main_window(|config| config.fonts(fonts))
+    }
+
+    /// Sets the main window configuration, replacing any options set
+    /// with the other builder methods.
+    pub fn window(mut self, config: WindowConfig) -> Self {
+        self.main_window = Some(config);
+        self
+    }
+
+    /// Only exit once every window has been closed. By default the app
+    /// exits as soon as the main window is closed.
+    pub fn exit_on_last_window(mut self, exit_on_last_window: bool) -> Self {
+        self.exit_on_last_window = exit_on_last_window;
+        self
+    }
+
+    /// Returns a handle to open and close windows while the app is running.
+    pub fn handle(&self) -> AppHandle {
+        AppHandle {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    pub fn run(mut self) -> Result<(), Error> {
+        let event_loop = EventLoop::new()?;
+        *self.shared.proxy.lock().unwrap() = Some(event_loop.create_proxy());
+
+        let result = event_loop.run_app(&mut self);
+        *self.shared.proxy.lock().unwrap() = None;
+        result?;
+
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Creates a window and the renderer state for it.
+    fn open_window(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        config: WindowConfig,
+    ) -> Result<WindowId, Error> {
+        let mut attrs = config.attributes.clone();
+        if let Some(icon) = &config.icon {
+            attrs.window_icon = Some(icon.load()?);
+        }
+        let window = Arc::new(event_loop.create_window(attrs)?);
+        let id = window.id();
+
+        let mut state = smol::block_on(State::new(Arc::clone(&window)))?;
+        if config.attributes.transparent {
+            state.set_transparent(true);
+        }
+        if let Some(fonts) = config.fonts {
+            state.set_fonts(fonts);
+        }
+        if config.root.is_some() && state.fonts().is_none() {
+            return Err(Error::MissingFonts);
+        }
+
+        let root = config.root.map(|root| WidgetNode::from_boxed(root));
+        self.windows.insert(id, AppWindow { state, root });
+        self.shared.windows.lock().unwrap().push(id);
+        window.request_redraw();
+
+        Ok(id)
+    }
+
+    /// Closes a window, exiting if it was the main window or, when
+    /// [`App::exit_on_last_window`] is set, the last window.
+    fn close_window(&mut self, event_loop: &ActiveEventLoop, id: WindowId) {
+        if self.windows.remove(&id).is_none() {
+            return;
+        }
+        self.shared
+            .windows
+            .lock()
+            .unwrap()
+            .retain(|window| *window != id);
+
+        let is_main = self.main_window_id == Some(id);
+        if self.windows.is_empty() || (is_main && !self.exit_on_last_window) {
+            event_loop.exit();
+        }
+    }
+
+    /// Stops the app because of an error.
+    fn fail(&mut self, event_loop: &ActiveEventLoop, err: Error) {
+        log::error!("{err}");
+        self.error = Some(err);
+        self.windows.clear();
+        event_loop.exit();
+    }
+
+    /// Handles the requests sent through app handles.
+    fn process_commands(&mut self, event_loop: &ActiveEventLoop) {
+        let commands = std::mem::take(&mut *self.shared.commands.lock().unwrap());
+
+        for command in commands {
+            match command {
+                Command::OpenWindow(config) => {
+                    if let Err(err) = self.open_window(event_loop, *config) {
+                        self.fail(event_loop, err);
+                        return;
+                    }
+                }
+                Command::CloseWindow(id) => self.close_window(event_loop, id),
+                Command::Exit => {
+                    self.windows.clear();
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self {
+            main_window: Some(WindowConfig::new()),
+            main_window_id: None,
+            windows: HashMap::new(),
+            exit_on_last_window: false,
+            shared: Arc::default(),
+            error: None,
+        }
+    }
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(config) = self.main_window.take() {
+            match self.open_window(event_loop, config) {
+                Ok(id) => self.main_window_id = Some(id),
+                Err(err) => {
+                    self.fail(event_loop, err);
+                    return;
+                }
+            }
+        }
+
+        self.process_commands(event_loop);
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, _event: ()) {
+        // Windows can only be created once the app has been resumed
+        if self.main_window.is_none() {
+            self.process_commands(event_loop);
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        // Drop the surfaces while the event loop, and therefore the windows, are still alive
+        self.windows.clear();
+        self.shared.windows.lock().unwrap().clear();
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+
+        match event {
+            WindowEvent::CloseRequested => {
+                self.close_window(event_loop, window_id);
+            }
+            WindowEvent::Resized(size) => {
+                window.state.resize(size);
+                if let Some(window) = window.state.window() {
+                    window.request_redraw();
+                }
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                // A resize event with the new physical size follows this
+                window.state.set_scale_factor(scale_factor);
+            }
+            WindowEvent::RedrawRequested => {
+                if let Err(err) = window.render() {
+                    self.fail(event_loop, err);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_attributes() {
+        let config = WindowConfig::new()
+            .title("Ruby")
+            .size(800.0, 600.0)
+            .min_size(200.0, 100.0)
+            .max_size(1600.0, 1200.0)
+            .resizable(false)
+            .decorations(false)
+            .transparent(true)
+            .maximized(true)
+            .fullscreen(true);
+        let attributes = &config.attributes;
+
+        assert_eq!(attributes.title, "Ruby");
+        assert_eq!(
+            attributes.inner_size,
+            Some(winit::dpi::LogicalSize::new(800.0, 600.0).into())
+        );
+        assert_eq!(
+            attributes.min_inner_size,
+            Some(winit::dpi::LogicalSize::new(200.0, 100.0).into())
+        );
+        assert_eq!(
+            attributes.max_inner_size,
+            Some(winit::dpi::LogicalSize::new(1600.0, 1200.0).into())
+        );
+        assert!(!attributes.resizable);
+        assert!(!attributes.decorations);
+        assert!(attributes.transparent);
+        assert!(attributes.maximized);
+        assert_eq!(attributes.fullscreen, Some(Fullscreen::Borderless(None)));
+    }
+
+    #[test]
+    fn default_window_attributes() {
+        let attributes = WindowConfig::new().attributes;
+        assert!(attributes.resizable);
+        assert!(attributes.decorations);
+        assert!(!attributes.transparent);
+        assert_eq!(attributes.fullscreen, None);
+
+        let config = WindowConfig::new().fullscreen(true).fullscreen(false);
+        assert_eq!(config.attributes.fullscreen, None);
+    }
+
+    #[test]
+    fn app_builder_configures_main_window() {
+        let app = App::new().title("Main").resizable(false);
+        let config = app.main_window.unwrap();
+        assert_eq!(config.attributes.title, "Main");
+        assert!(!config.attributes.resizable);
+
+        assert!(App::default().main_window.is_some());
+    }
+
+    #[test]
+    fn icon_from_bytes() {
+        let mut png = Vec::new();
+        image::RgbaImage::new(4, 2)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let config = WindowConfig::new().icon_from_bytes(png);
+        assert!(config.icon.unwrap().load().is_ok());
+    }
+
+    #[test]
+    fn invalid_icon() {
+        let icon = IconSource::Bytes(b"not an image".to_vec());
+        assert!(matches!(icon.load(), Err(Error::LoadIcon(_))));
+    }
+}
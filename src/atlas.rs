@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Where an entry was put in an [`AtlasAllocator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Allocation {
+    /// The index of the page the entry is on.
+    pub page: usize,
+    /// The area of the page the entry can use in pixels, as
+    /// `[x, y, width, height]`, not including padding.
+    pub rect: [u32; 4],
+}
+
+/// Packs rectangles into square pages, for atlas textures that many
+/// small images or glyphs share.
+///
+/// Entries are packed on shelves: rows as tall as the entries on them,
+/// filled left to right. When every page is full a new page is added,
+/// up to a limit, after which the least recently used entries are
+/// evicted to make room. Entries used in the current frame are never
+/// evicted, as they're referenced by the frame being drawn.
+#[derive(Debug, Clone)]
+pub(crate) struct AtlasAllocator<K> {
+    /// The width and height of each page that can be allocated.
+    size: [u32; 2],
+    /// The gap left after each entry, so entries don't bleed into each
+    /// other when sampled between pixels.
+    padding: u32,
+    max_pages: usize,
+    pages: Vec<Page>,
+    entries: HashMap<K, Entry>,
+    frame: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    allocation: Allocation,
+    /// The frame the entry was last used in.
+    last_used: u64,
+}
+
+impl<K: Hash + Eq + Clone> AtlasAllocator<K> {
+    /// Creates an allocator with no pages, whose pages are `size`
+    /// pixels wide and tall.
+    pub fn new(size: [u32; 2], padding: u32, max_pages: usize) -> Self {
+        Self {
+            size,
+            padding,
+            max_pages: max_pages.max(1),
+            pages: Vec::new(),
+            entries: HashMap::new(),
+            frame: 0,
+        }
+    }
+
+    /// The number of pages in use, which only grows.
+    pub fn pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Starts a new frame, after which entries that aren't used again
+    /// can be evicted.
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Returns where `key` is, marking it as used in this frame.
+    pub fn get(&mut self, key: &K) -> Option<Allocation> {
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.frame;
+        Some(entry.allocation)
+    }
+
+    /// Finds space for a `width` by `height` entry, adding a page or
+    /// evicting old entries if it doesn't fit.
+    ///
+    /// Returns `None` if the entry is larger than a page, or if every
+    /// entry that could be evicted has been used in this frame.
+    pub fn insert(&mut self, key: K, width: u32, height: u32) -> Option<Allocation> {
+        self.remove(&key);
+        let (padded_width, padded_height) = (width + self.padding, height + self.padding);
+        if padded_width > self.size[0] || padded_height > self.size[1] {
+            return None;
+        }
+
+        loop {
+            let position = self
+                .pages
+                .iter_mut()
+                .enumerate()
+                .find_map(|(page, shelves)| {
+                    let [x, y] = shelves.allocate(padded_width, padded_height, self.size)?;
+                    Some((page, x, y))
+                });
+            if let Some((page, x, y)) = position {
+                let allocation = Allocation {
+                    page,
+                    rect: [x, y, width, height],
+                };
+                let entry = Entry {
+                    allocation,
+                    last_used: self.frame,
+                };
+                self.entries.insert(key, entry);
+                return Some(allocation);
+            }
+
+            if self.pages.len() < self.max_pages {
+                self.pages.push(Page::default());
+                continue;
+            }
+
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|(_, entry)| entry.last_used < self.frame)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())?;
+            self.remove(&oldest);
+        }
+    }
+
+    /// Frees the space of `key`, returning whether it was allocated.
+    pub fn remove(&mut self, key: &K) -> bool {
+        let Some(entry) = self.entries.remove(key) else {
+            return false;
+        };
+
+        let [x, y, ..] = entry.allocation.rect;
+        self.pages[entry.allocation.page].free(x, y);
+        true
+    }
+}
+
+/// The shelves of a page, from top to bottom.
+#[derive(Debug, Clone, Default)]
+struct Page {
+    shelves: Vec<Shelf>,
+}
+
+impl Page {
+    /// Finds space for an entry on the shortest shelf it fits on, or
+    /// on a new shelf below the others.
+    fn allocate(
+        &mut self,
+        width: u32,
+        height: u32,
+        [page_width, page_height]: [u32; 2],
+    ) -> Option<[u32; 2]> {
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height && shelf.fits(width, page_width))
+            .min_by_key(|shelf| shelf.height);
+        if let Some(shelf) = shelf {
+            return Some([shelf.insert(width), shelf.y]);
+        }
+
+        let y = self
+            .shelves
+            .last()
+            .map_or(0, |shelf| shelf.y + shelf.height);
+        if y + height > page_height {
+            return None;
+        }
+        // Rounding up lets entries of similar heights share shelves
+        let height = height.next_multiple_of(Shelf::ALIGN).min(page_height - y);
+        self.shelves.push(Shelf {
+            y,
+            height,
+            slots: Vec::new(),
+        });
+        let shelf = self.shelves.last_mut().unwrap();
+        Some([shelf.insert(width), y])
+    }
+
+    /// Frees the entry at `x` on the shelf at `y`, removing shelves
+    /// at the bottom of the page that become empty.
+    fn free(&mut self, x: u32, y: u32) {
+        if let Some(shelf) = self.shelves.iter_mut().find(|shelf| shelf.y == y) {
+            shelf.free(x);
+        }
+        while self
+            .shelves
+            .last()
+            .is_some_and(|shelf| shelf.slots.is_empty())
+        {
+            self.shelves.pop();
+        }
+    }
+}
+
+/// A row of entries, divided into slots that are used or free.
+#[derive(Debug, Clone)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// The slots from left to right, space after the last slot is
+    /// unused. Neighbouring free slots are merged.
+    slots: Vec<Slot>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    x: u32,
+    width: u32,
+    free: bool,
+}
+
+impl Shelf {
+    /// The multiple that shelf heights are rounded up to.
+    const ALIGN: u32 = 4;
+
+    fn end(&self) -> u32 {
+        self.slots.last().map_or(0, |slot| slot.x + slot.width)
+    }
+
+    fn fits(&self, width: u32, page_width: u32) -> bool {
+        self.end() + width <= page_width
+            || self
+                .slots
+                .iter()
+                .any(|slot| slot.free && slot.width >= width)
+    }
+
+    /// Takes space for an entry that [`Shelf::fits`], preferring the
+    /// narrowest free slot, and returns its x.
+    fn insert(&mut self, width: u32) -> u32 {
+        let free = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.free && slot.width >= width)
+            .min_by_key(|(_, slot)| slot.width)
+            .map(|(i, _)| i);
+
+        let Some(i) = free else {
+            let x = self.end();
+            self.slots.push(Slot {
+                x,
+                width,
+                free: false,
+            });
+            return x;
+        };
+
+        let slot = self.slots[i];
+        self.slots[i] = Slot {
+            width,
+            free: false,
+            ..slot
+        };
+        if slot.width > width {
+            let rest = Slot {
+                x: slot.x + width,
+                width: slot.width - width,
+                free: true,
+            };
+            self.slots.insert(i + 1, rest);
+        }
+        slot.x
+    }
+
+    fn free(&mut self, x: u32) {
+        let Some(mut i) = self.slots.iter().position(|slot| slot.x == x) else {
+            return;
+        };
+        self.slots[i].free = true;
+
+        if self.slots.get(i + 1).is_some_and(|next| next.free) {
+            self.slots[i].width += self.slots.remove(i + 1).width;
+        }
+        if i > 0 && self.slots[i - 1].free {
+            self.slots[i - 1].width += self.slots.remove(i).width;
+            i -= 1;
+        }
+        // Free space at the end of the shelf is unused space
+        if i == self.slots.len() - 1 {
+            self.slots.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether two allocations on the same page overlap.
+    fn overlap(a: &Allocation, b: &Allocation) -> bool {
+        let ([ax, ay, aw, ah], [bx, by, bw, bh]) = (a.rect, b.rect);
+        a.page == b.page && ax < bx + bw && bx < ax + aw && ay < by + bh && by < ay + ah
+    }
+
+    #[test]
+    fn pack_without_overlap() {
+        let mut atlas = AtlasAllocator::new([128, 128], 1, 1);
+        let mut allocations = Vec::new();
+        for i in 0..40u32 {
+            let (width, height) = (3 + i * 7 % 13, 2 + i * 5 % 11);
+            let allocation = atlas.insert(i, width, height).unwrap();
+            let [x, y, w, h] = allocation.rect;
+            assert_eq!((w, h), (width, height));
+            assert!(x + w < 128 && y + h < 128);
+            allocations.push(allocation);
+        }
+
+        for (i, a) in allocations.iter().enumerate() {
+            for b in &allocations[i + 1..] {
+                assert!(!overlap(a, b), "{a:?} overlaps {b:?}");
+            }
+        }
+        assert_eq!(atlas.pages(), 1);
+        assert_eq!(atlas.get(&3), Some(allocations[3]));
+    }
+
+    #[test]
+    fn reuse_freed_space() {
+        let mut atlas = AtlasAllocator::new([64, 64], 0, 1);
+        let a = atlas.insert("a", 20, 10).unwrap();
+        let b = atlas.insert("b", 20, 10).unwrap();
+        atlas.insert("c", 20, 10).unwrap();
+
+        assert!(atlas.remove(&"b"));
+        assert!(!atlas.remove(&"b"));
+        // A narrower entry takes the freed slot, leaving the rest free
+        let d = atlas.insert("d", 12, 8).unwrap();
+        assert_eq!(d.rect[..2], b.rect[..2]);
+        let e = atlas.insert("e", 8, 8).unwrap();
+        assert_eq!(e.rect[..2], [b.rect[0] + 12, b.rect[1]]);
+
+        // Emptying the page frees its shelves
+        for key in ["a", "c", "d", "e"] {
+            atlas.remove(&key);
+        }
+        let tall = atlas.insert("tall", 64, 64).unwrap();
+        assert_eq!(tall.rect, [0, 0, 64, 64]);
+        assert_eq!(a.rect[..2], [0, 0]);
+    }
+
+    #[test]
+    fn grow_to_new_pages() {
+        let mut atlas = AtlasAllocator::new([32, 32], 0, 3);
+        let allocations: Vec<_> = (0..8).map(|i| atlas.insert(i, 16, 16).unwrap()).collect();
+
+        assert_eq!(atlas.pages(), 2);
+        assert!(allocations[..4].iter().all(|a| a.page == 0));
+        assert!(allocations[4..].iter().all(|a| a.page == 1));
+        assert_eq!(atlas.insert(8, 33, 1), None);
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let mut atlas = AtlasAllocator::new([32, 32], 0, 1);
+        for i in 0..4 {
+            atlas.insert(i, 16, 16).unwrap();
+            atlas.next_frame();
+        }
+        // 0 is the oldest, but was used again
+        atlas.get(&0);
+        atlas.next_frame();
+
+        let allocation = atlas.insert(4, 16, 16).unwrap();
+        assert_eq!(atlas.get(&1), None);
+        assert_eq!(allocation.rect[..2], [16, 0]);
+        assert!(atlas.get(&0).is_some());
+        assert_eq!(atlas.len(), 4);
+    }
+
+    #[test]
+    fn keep_entries_used_this_frame() {
+        let mut atlas = AtlasAllocator::new([32, 32], 0, 1);
+        for i in 0..4 {
+            atlas.insert(i, 16, 16).unwrap();
+        }
+        assert_eq!(atlas.insert(4, 16, 16), None);
+        assert_eq!(atlas.len(), 4);
+
+        // Large entries evict as many entries as they need
+        atlas.next_frame();
+        assert_eq!(atlas.insert(5, 32, 32).unwrap().rect, [0, 0, 32, 32]);
+        assert_eq!(atlas.len(), 1);
+    }
+}
@@ -0,0 +1,392 @@
+use std::f32::consts::TAU;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{Color, Position, Size};
+
+/// A color at a point along a gradient.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct GradientStop {
+    /// Where the stop is along the gradient, from 0 to 1.
+    pub offset: f32,
+    pub color: Color,
+}
+
+impl GradientStop {
+    /// Creates a new [`GradientStop`].
+    pub const fn new(offset: f32, color: Color) -> Self {
+        Self { offset, color }
+    }
+}
+
+/// A gradient along a straight line through the center of a shape.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
+pub struct LinearGradient {
+    /// The direction of the gradient in radians, clockwise from
+    /// pointing right. An angle of zero goes from left to right.
+    pub angle: f32,
+    pub stops: Vec<GradientStop>,
+}
+
+impl LinearGradient {
+    /// Creates a [`LinearGradient`] with no stops.
+    pub const fn new(angle: f32) -> Self {
+        Self {
+            angle,
+            stops: Vec::new(),
+        }
+    }
+
+    /// Adds a color stop.
+    pub fn stop(mut self, offset: f32, color: Color) -> Self {
+        self.stops.push(GradientStop::new(offset, color));
+        self
+    }
+}
+
+/// A gradient radiating out from a center point.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct RadialGradient {
+    /// The center of the gradient as a fraction of the size of the
+    /// shape, `(0.5, 0.5)` is the middle.
+    pub center: Position,
+    /// The radius of the gradient, or `None` to reach the farthest
+    /// corner of the shape.
+    pub radius: Option<f32>,
+    pub stops: Vec<GradientStop>,
+}
+
+impl Default for RadialGradient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RadialGradient {
+    /// Creates a [`RadialGradient`] centered in the shape that reaches
+    /// the corners, with no stops.
+    pub const fn new() -> Self {
+        Self {
+            center: Position::new(0.5, 0.5),
+            radius: None,
+            stops: Vec::new(),
+        }
+    }
+
+    /// Sets the center, as a fraction of the size of the shape.
+    pub const fn center(mut self, x: f32, y: f32) -> Self {
+        self.center = Position::new(x, y);
+        self
+    }
+
+    /// Sets the radius.
+    pub const fn radius(mut self, radius: f32) -> Self {
+        self.radius = Some(radius);
+        self
+    }
+
+    /// Adds a color stop.
+    pub fn stop(mut self, offset: f32, color: Color) -> Self {
+        self.stops.push(GradientStop::new(offset, color));
+        self
+    }
+}
+
+/// A gradient that sweeps around a center point.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct ConicGradient {
+    /// The center of the gradient as a fraction of the size of the
+    /// shape, `(0.5, 0.5)` is the middle.
+    pub center: Position,
+    /// Where the gradient starts in radians, clockwise from pointing right.
+    pub angle: f32,
+    pub stops: Vec<GradientStop>,
+}
+
+impl Default for ConicGradient {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+impl ConicGradient {
+    /// Creates a [`ConicGradient`] centered in the shape, with no stops.
+    pub const fn new(angle: f32) -> Self {
+        Self {
+            center: Position::new(0.5, 0.5),
+            angle,
+            stops: Vec::new(),
+        }
+    }
+
+    /// Sets the center, as a fraction of the size of the shape.
+    pub const fn center(mut self, x: f32, y: f32) -> Self {
+        self.center = Position::new(x, y);
+        self
+    }
+
+    /// Adds a color stop.
+    pub fn stop(mut self, offset: f32, color: Color) -> Self {
+        self.stops.push(GradientStop::new(offset, color));
+        self
+    }
+}
+
+/// How a shape is filled.
+///
+/// Gradient colors are converted to linear color space and interpolated
+/// with premultiplied alpha. Before the first stop and after the last,
+/// the color of the nearest stop is used.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Brush {
+    Solid(Color),
+    Linear(LinearGradient),
+    Radial(RadialGradient),
+    Conic(ConicGradient),
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self::Solid(Color::TRANSPARENT)
+    }
+}
+
+impl From<Color> for Brush {
+    fn from(color: Color) -> Self {
+        Self::Solid(color)
+    }
+}
+
+impl From<LinearGradient> for Brush {
+    fn from(gradient: LinearGradient) -> Self {
+        Self::Linear(gradient)
+    }
+}
+
+impl From<RadialGradient> for Brush {
+    fn from(gradient: RadialGradient) -> Self {
+        Self::Radial(gradient)
+    }
+}
+
+impl From<ConicGradient> for Brush {
+    fn from(gradient: ConicGradient) -> Self {
+        Self::Conic(gradient)
+    }
+}
+
+impl Brush {
+    /// The color stops of the gradient, empty for solid colors.
+    pub fn stops(&self) -> &[GradientStop] {
+        match self {
+            Self::Solid(_) => &[],
+            Self::Linear(gradient) => &gradient.stops,
+            Self::Radial(gradient) => &gradient.stops,
+            Self::Conic(gradient) => &gradient.stops,
+        }
+    }
+
+    /// The parameters the shader needs to fill a shape of `size` with
+    /// this brush, relative to the center of the shape.
+    pub(crate) fn params(&self, size: Size) -> BrushParams {
+        let (hw, hh) = (size.width / 2.0, size.height / 2.0);
+        let center = |center: Position| {
+            [
+                (center.x - 0.5) * size.width,
+                (center.y - 0.5) * size.height,
+            ]
+        };
+
+        let (kind, params) = match self {
+            Self::Solid(_) => (BrushParams::SOLID, [0.0; 4]),
+            Self::Linear(gradient) => {
+                let (sin, cos) = gradient.angle.sin_cos();
+                // The gradient line is long enough for the corners of
+                // the shape to be at 0 and 1
+                let length = (hw * cos).abs() + (hh * sin).abs();
+                let scale = if length > 0.0 { 0.5 / length } else { 0.0 };
+                (BrushParams::LINEAR, [cos * scale, sin * scale, 0.0, 0.0])
+            }
+            Self::Radial(gradient) => {
+                let [x, y] = center(gradient.center);
+                let radius = gradient.radius.unwrap_or_else(|| {
+                    let dx = hw + x.abs();
+                    let dy = hh + y.abs();
+                    (dx * dx + dy * dy).sqrt()
+                });
+                let scale = if radius > 0.0 { 1.0 / radius } else { 0.0 };
+                (BrushParams::RADIAL, [x, y, scale, 0.0])
+            }
+            Self::Conic(gradient) => {
+                let [x, y] = center(gradient.center);
+                (
+                    BrushParams::CONIC,
+                    [x, y, gradient.angle.rem_euclid(TAU), 0.0],
+                )
+            }
+        };
+
+        BrushParams {
+            kind,
+            stops: self.stops().len() as u32,
+            params,
+        }
+    }
+
+    /// The linear color of vertices filled with this brush.
+    pub(crate) fn color(&self) -> [f32; 4] {
+        match self {
+            Self::Solid(color) => color.to_linear(),
+            _ => [1.0; 4],
+        }
+    }
+
+    /// The linear color of the brush at `point`, relative to the center
+    /// of a shape of `size`, matching the rect shader.
+    ///
+    /// Stops are blended with premultiplied alpha, the returned color
+    /// isn't premultiplied.
+    pub(crate) fn color_at(&self, point: Position, size: Size) -> [f32; 4] {
+        let mut stops: Vec<StopData> = self.stops().iter().map(StopData::from).collect();
+        if stops.is_empty() {
+            return self.color();
+        }
+        stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+        let BrushParams { kind, params, .. } = self.params(size);
+        let t = match kind {
+            BrushParams::LINEAR => point.x * params[0] + point.y * params[1] + 0.5,
+            BrushParams::RADIAL => (point.x - params[0]).hypot(point.y - params[1]) * params[2],
+            _ => {
+                let angle = (point.y - params[1]).atan2(point.x - params[0]);
+                ((angle - params[2]) / TAU + 1.0).fract()
+            }
+        };
+
+        let premultiply = |[r, g, b, a]: [f32; 4]| [r * a, g * a, b * a, a];
+        let next = stops.iter().position(|stop| t <= stop.offset);
+        let [r, g, b, a] = match next {
+            Some(0) => premultiply(stops[0].color),
+            None => premultiply(stops[stops.len() - 1].color),
+            Some(i) => {
+                let (previous, next) = (&stops[i - 1], &stops[i]);
+                let f = (t - previous.offset) / (next.offset - previous.offset).max(1e-6);
+                let (from, to) = (premultiply(previous.color), premultiply(next.color));
+                std::array::from_fn(|c| from[c] + (to[c] - from[c]) * f)
+            }
+        };
+
+        if a > 0.0 {
+            [r / a, g / a, b / a, a]
+        } else {
+            [0.0; 4]
+        }
+    }
+}
+
+/// The per vertex data describing a [`Brush`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BrushParams {
+    pub kind: u32,
+    pub stops: u32,
+    pub params: [f32; 4],
+}
+
+impl BrushParams {
+    pub const SOLID: u32 = 0;
+    pub const LINEAR: u32 = 1;
+    pub const RADIAL: u32 = 2;
+    pub const CONIC: u32 = 3;
+}
+
+/// A gradient stop laid out for the gradient storage buffer.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Default, Zeroable)]
+pub(crate) struct StopData {
+    pub color: [f32; 4],
+    pub offset: f32,
+    _padding: [f32; 3],
+}
+
+impl From<&GradientStop> for StopData {
+    fn from(stop: &GradientStop) -> Self {
+        Self {
+            color: stop.color.to_linear(),
+            offset: stop.offset.clamp(0.0, 1.0),
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn linear_gradient_reaches_corners() {
+        let brush = Brush::from(LinearGradient::new(0.0).stop(0.0, Color::BLACK));
+        let params = brush.params(Size::new(200.0, 100.0));
+
+        assert_eq!(params.kind, BrushParams::LINEAR);
+        assert_eq!(params.stops, 1);
+        // t = dot(local, direction) + 0.5, so the left edge is 0 and
+        // the right edge is 1
+        assert_eq!(params.params[0] * -100.0 + 0.5, 0.0);
+        assert_eq!(params.params[0] * 100.0 + 0.5, 1.0);
+
+        let brush = Brush::from(LinearGradient::new(FRAC_PI_2));
+        let params = brush.params(Size::new(200.0, 100.0));
+        assert!((params.params[1] * 50.0 + 0.5 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn radial_gradient_defaults_to_farthest_corner() {
+        let brush = Brush::from(RadialGradient::new().center(0.0, 0.0));
+        let params = brush.params(Size::new(30.0, 40.0));
+
+        assert_eq!(params.kind, BrushParams::RADIAL);
+        assert_eq!(params.params[0], -15.0);
+        assert_eq!(params.params[1], -20.0);
+        assert_eq!(params.params[2], 1.0 / 50.0);
+    }
+
+    #[test]
+    fn sample_gradient_colors() {
+        let size = Size::new(200.0, 100.0);
+        let brush = Brush::from(
+            LinearGradient::new(0.0)
+                .stop(1.0, Color::WHITE)
+                .stop(0.0, Color::BLACK),
+        );
+        assert_eq!(
+            brush.color_at(Position::new(-100.0, 0.0), size),
+            [0.0, 0.0, 0.0, 1.0]
+        );
+        assert_eq!(
+            brush.color_at(Position::new(0.0, 50.0), size),
+            [0.5, 0.5, 0.5, 1.0]
+        );
+        assert_eq!(brush.color_at(Position::new(150.0, 0.0), size), [1.0; 4]);
+
+        let brush = Brush::from(RadialGradient::new().radius(10.0).stop(0.5, Color::RED));
+        assert_eq!(
+            brush.color_at(Position::new(3.0, 4.0), size),
+            [1.0, 0.0, 0.0, 1.0]
+        );
+        assert_eq!(
+            Brush::from(Color::RED).color_at(Position::ZERO, size),
+            [1.0, 0.0, 0.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn solid_brush_has_no_stops() {
+        let brush = Brush::from(Color::RED);
+        let params = brush.params(Size::new(30.0, 40.0));
+
+        assert_eq!(params.kind, BrushParams::SOLID);
+        assert_eq!(params.stops, 0);
+        assert_eq!(brush.color(), [1.0, 0.0, 0.0, 1.0]);
+    }
+}
@@ -0,0 +1,254 @@
+use bytemuck::{Pod, Zeroable};
+use winit::dpi::PhysicalSize;
+
+use crate::{CornerRadii, Rect, Transform};
+
+/// A clip shape laid out for the clip uniform buffer, used when a
+/// clip can't be expressed as a scissor rect.
+///
+/// The fragment shaders map each pixel back into the coordinates the
+/// clip was pushed in, so rounded and transformed clips are exact.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Default, Zeroable)]
+pub(crate) struct ClipShape {
+    /// The clip rectangle, in the coordinates it was pushed in.
+    pub rect: [f32; 4],
+    pub radii: [f32; 4],
+    /// The linear part of the inverse of the transform the clip was
+    /// pushed with, and the translation after it.
+    pub inverse: [f32; 4],
+    pub translation: [f32; 2],
+    /// Zero for the shape that doesn't clip anything.
+    pub enabled: u32,
+    _padding: u32,
+}
+
+impl ClipShape {
+    fn new(rect: Rect, radii: CornerRadii, inverse: Transform) -> Self {
+        let [a, b, c, d, e, f] = inverse.to_array();
+        Self {
+            rect: [rect.x(), rect.y(), rect.width(), rect.height()],
+            radii: radii.to_array(),
+            inverse: [a, b, c, d],
+            translation: [e, f],
+            enabled: 1,
+            _padding: 0,
+        }
+    }
+
+    fn rect(&self) -> Rect {
+        let [x, y, width, height] = self.rect;
+        Rect::new(x, y, width, height)
+    }
+
+    /// Whether both shapes were pushed with the same transform, so
+    /// their rectangles can be intersected directly.
+    fn same_space(&self, other: &Self) -> bool {
+        self.inverse == other.inverse && self.translation == other.translation
+    }
+
+    /// The intersection of two shapes in the same space.
+    ///
+    /// Each corner keeps the radius of the shape it came from, corners
+    /// where the edges of both shapes cross are sharp.
+    fn intersect(&self, other: &Self) -> Self {
+        let (a, b) = (self.rect(), other.rect());
+        let rect = a.intersection(b);
+
+        // Whether each edge of the intersection comes from `self`,
+        // ties go to the inner clip
+        let left = a.x() > b.x();
+        let top = a.y() > b.y();
+        let right = a.right() < b.right();
+        let bottom = a.bottom() < b.bottom();
+
+        let corner = |i: usize, x: bool, y: bool| match (x, y) {
+            (true, true) => self.radii[i],
+            (false, false) => other.radii[i],
+            _ => 0.0,
+        };
+        let radii = [
+            corner(0, left, top),
+            corner(1, right, top),
+            corner(2, right, bottom),
+            corner(3, left, bottom),
+        ];
+
+        Self {
+            rect: [rect.x(), rect.y(), rect.width(), rect.height()],
+            radii,
+            ..*other
+        }
+    }
+}
+
+/// The region drawing is restricted to.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct Clip {
+    /// The bounds of every clip, in logical pixels, which are applied
+    /// with a scissor rect. `None` draws to the whole target.
+    pub scissor: Option<Rect>,
+    /// The innermost clip that isn't an axis-aligned rectangle.
+    pub shape: Option<ClipShape>,
+}
+
+impl Clip {
+    /// Returns this clip intersected with `rect`, which is transformed
+    /// by `transform`.
+    ///
+    /// Axis-aligned rectangles only narrow the scissor rect. Other
+    /// clips also narrow it to their bounds and are clipped exactly by
+    /// the shaders, which can only intersect clips pushed with the same
+    /// transform, otherwise the inner clip replaces the outer shape.
+    pub fn intersect(self, rect: Rect, radii: CornerRadii, transform: Transform) -> Self {
+        let bounds = transform.transform_rect(rect);
+        let scissor = match self.scissor {
+            Some(scissor) => scissor.intersection(bounds),
+            None => bounds,
+        };
+
+        if transform.is_axis_aligned() && radii == CornerRadii::default() {
+            return Self {
+                scissor: Some(scissor),
+                ..self
+            };
+        }
+
+        // A transform without an inverse flattens the clip to a line
+        let Some(inverse) = transform.invert() else {
+            return Self {
+                scissor: Some(Rect::default()),
+                shape: None,
+            };
+        };
+
+        let mut shape = ClipShape::new(rect, radii, inverse);
+        if let Some(outer) = self.shape
+            && outer.same_space(&shape)
+        {
+            shape = outer.intersect(&shape);
+        }
+
+        Self {
+            scissor: Some(scissor),
+            shape: Some(shape),
+        }
+    }
+}
+
+/// Converts a scissor rect in logical pixels to the physical pixels
+/// of a target of `size`, as `[x, y, width, height]`.
+///
+/// Edges are rounded to the nearest pixel. Returns `None` if nothing
+/// of the target is left to draw to.
+pub(crate) fn scissor_rect(
+    rect: Rect,
+    scale_factor: f64,
+    size: PhysicalSize<u32>,
+) -> Option<[u32; 4]> {
+    let scale = scale_factor as f32;
+    let to_pixels = |value: f32, max: u32| ((value * scale).round().max(0.0) as u32).min(max);
+
+    let x = to_pixels(rect.x(), size.width);
+    let y = to_pixels(rect.y(), size.height);
+    let right = to_pixels(rect.right(), size.width);
+    let bottom = to_pixels(rect.bottom(), size.height);
+
+    (right > x && bottom > y).then(|| [x, y, right - x, bottom - y])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_rects_intersect() {
+        let clip = Clip::default()
+            .intersect(
+                Rect::new(0.0, 0.0, 100.0, 100.0),
+                CornerRadii::default(),
+                Transform::IDENTITY,
+            )
+            .intersect(
+                Rect::new(10.0, 50.0, 20.0, 100.0),
+                CornerRadii::default(),
+                Transform::translate(5.0, 0.0),
+            );
+
+        assert_eq!(clip.scissor, Some(Rect::new(15.0, 50.0, 20.0, 50.0)));
+        assert_eq!(clip.shape, None);
+    }
+
+    #[test]
+    fn rounded_and_rotated_clips_use_shapes() {
+        let rounded = Clip::default().intersect(
+            Rect::new(0.0, 0.0, 100.0, 100.0),
+            CornerRadii::all(8.0),
+            Transform::IDENTITY,
+        );
+        assert_eq!(rounded.scissor, Some(Rect::new(0.0, 0.0, 100.0, 100.0)));
+        assert_eq!(rounded.shape.unwrap().radii, [8.0; 4]);
+
+        let rotated = Clip::default().intersect(
+            Rect::new(0.0, 0.0, 10.0, 20.0),
+            CornerRadii::default(),
+            Transform::rotate(std::f32::consts::FRAC_PI_2),
+        );
+        let shape = rotated.shape.unwrap();
+        assert_eq!(shape.rect, [0.0, 0.0, 10.0, 20.0]);
+        // Maps a point in the rotated clip back into the clip rect
+        let [a, b, c, d] = shape.inverse;
+        let (x, y) = (-15.0, 5.0);
+        let local = (a * x + c * y, b * x + d * y);
+        assert!((local.0 - 5.0).abs() < 1e-4 && (local.1 - 15.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn nested_shapes_keep_outer_corners() {
+        let clip = Clip::default()
+            .intersect(
+                Rect::new(0.0, 0.0, 100.0, 100.0),
+                CornerRadii::all(8.0),
+                Transform::IDENTITY,
+            )
+            .intersect(
+                Rect::new(50.0, -10.0, 100.0, 50.0),
+                CornerRadii::all(4.0),
+                Transform::IDENTITY,
+            );
+
+        let shape = clip.shape.unwrap();
+        assert_eq!(shape.rect, [50.0, 0.0, 50.0, 40.0]);
+        // The top right corner is the outer clip's, the bottom left the
+        // inner clip's and the others are where the edges cross
+        assert_eq!(shape.radii, [0.0, 8.0, 0.0, 4.0]);
+    }
+
+    #[test]
+    fn singular_transform_clips_everything() {
+        let clip = Clip::default().intersect(
+            Rect::new(0.0, 0.0, 100.0, 100.0),
+            CornerRadii::all(8.0),
+            Transform::scale(0.0, 1.0),
+        );
+        assert!(clip.scissor.unwrap().is_empty());
+    }
+
+    #[test]
+    fn scissor_in_physical_pixels() {
+        let size = PhysicalSize::new(200, 100);
+
+        assert_eq!(
+            scissor_rect(Rect::new(10.2, 20.0, 30.0, 30.2), 2.0, size),
+            Some([20, 40, 60, 60])
+        );
+        assert_eq!(
+            scissor_rect(Rect::new(-10.0, 80.0, 300.0, 50.0), 1.0, size),
+            Some([0, 80, 200, 20])
+        );
+        assert_eq!(
+            scissor_rect(Rect::new(300.0, 0.0, 10.0, 10.0), 1.0, size),
+            None
+        );
+    }
+}
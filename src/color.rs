@@ -0,0 +1,311 @@
+use std::str::FromStr;
+
+/// An RGBA color in the sRGB color space, with straight alpha.
+///
+/// Components are from 0 to 1 and are gamma encoded, the same as CSS
+/// and most design tools. Colors are converted to linear space with
+/// [`Color::to_linear`] before they're sent to the GPU, so that
+/// blending and gradients on sRGB render targets are correct.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const TRANSPARENT: Self = Self::rgba(0.0, 0.0, 0.0, 0.0);
+    pub const BLACK: Self = Self::rgb8(0, 0, 0);
+    pub const WHITE: Self = Self::rgb8(255, 255, 255);
+    pub const GRAY: Self = Self::rgb8(128, 128, 128);
+    pub const SILVER: Self = Self::rgb8(192, 192, 192);
+    pub const RED: Self = Self::rgb8(255, 0, 0);
+    pub const MAROON: Self = Self::rgb8(128, 0, 0);
+    pub const ORANGE: Self = Self::rgb8(255, 165, 0);
+    pub const YELLOW: Self = Self::rgb8(255, 255, 0);
+    pub const OLIVE: Self = Self::rgb8(128, 128, 0);
+    pub const LIME: Self = Self::rgb8(0, 255, 0);
+    pub const GREEN: Self = Self::rgb8(0, 128, 0);
+    pub const AQUA: Self = Self::rgb8(0, 255, 255);
+    pub const TEAL: Self = Self::rgb8(0, 128, 128);
+    pub const BLUE: Self = Self::rgb8(0, 0, 255);
+    pub const NAVY: Self = Self::rgb8(0, 0, 128);
+    pub const FUCHSIA: Self = Self::rgb8(255, 0, 255);
+    pub const PURPLE: Self = Self::rgb8(128, 0, 128);
+
+    /// The named colors, using their CSS names.
+    const NAMED: [(&str, Self); 18] = [
+        ("transparent", Self::TRANSPARENT),
+        ("black", Self::BLACK),
+        ("white", Self::WHITE),
+        ("gray", Self::GRAY),
+        ("silver", Self::SILVER),
+        ("red", Self::RED),
+        ("maroon", Self::MAROON),
+        ("orange", Self::ORANGE),
+        ("yellow", Self::YELLOW),
+        ("olive", Self::OLIVE),
+        ("lime", Self::LIME),
+        ("green", Self::GREEN),
+        ("aqua", Self::AQUA),
+        ("teal", Self::TEAL),
+        ("blue", Self::BLUE),
+        ("navy", Self::NAVY),
+        ("fuchsia", Self::FUCHSIA),
+        ("purple", Self::PURPLE),
+    ];
+
+    /// Creates an opaque [`Color`] from sRGB components.
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::rgba(r, g, b, 1.0)
+    }
+
+    /// Creates a [`Color`] from sRGB components.
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Creates an opaque [`Color`] from 8-bit sRGB components.
+    pub const fn rgb8(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba8(r, g, b, 255)
+    }
+
+    /// Creates a [`Color`] from 8-bit sRGB components.
+    pub const fn rgba8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::rgba(
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        )
+    }
+
+    /// Creates an opaque [`Color`] from a hue in degrees, and a
+    /// saturation and lightness from 0 to 1.
+    pub fn hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        Self::hsla(hue, saturation, lightness, 1.0)
+    }
+
+    /// Creates a [`Color`] from a hue in degrees, and a saturation,
+    /// lightness and alpha from 0 to 1.
+    pub fn hsla(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Self::from_hue(hue, chroma, lightness - chroma / 2.0, alpha)
+    }
+
+    /// Creates an opaque [`Color`] from a hue in degrees, and a
+    /// saturation and value from 0 to 1.
+    pub fn hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        Self::hsva(hue, saturation, value, 1.0)
+    }
+
+    /// Creates a [`Color`] from a hue in degrees, and a saturation,
+    /// value and alpha from 0 to 1.
+    pub fn hsva(hue: f32, saturation: f32, value: f32, alpha: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+
+        let chroma = value * saturation;
+        Self::from_hue(hue, chroma, value - chroma, alpha)
+    }
+
+    /// Creates a color from the hue, chroma and the amount added to
+    /// every component, which is shared by HSL and HSV.
+    fn from_hue(hue: f32, chroma: f32, m: f32, alpha: f32) -> Self {
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+
+        let (r, g, b) = match hue as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        Self::rgba(r + m, g + m, b + m, alpha)
+    }
+
+    /// Parses a hex color in the form `#rgb`, `#rrggbb` or `#rrggbbaa`,
+    /// the leading `#` is optional.
+    pub fn hex(hex: &str) -> Result<Self, ParseColorError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        // `from_str_radix` would accept a leading `+`
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ParseColorError::InvalidHex(hex.to_owned()));
+        }
+
+        let parse = |digits: &str| {
+            u8::from_str_radix(digits, 16).map_err(|_| ParseColorError::InvalidHex(hex.to_owned()))
+        };
+
+        match digits.len() {
+            3 => {
+                let [r, g, b] = [0, 1, 2].map(|i| parse(&digits[i..=i]));
+                // Each digit is repeated, so `f` is `ff`
+                Ok(Self::rgb8(r? * 17, g? * 17, b? * 17))
+            }
+            6 | 8 => {
+                let r = parse(&digits[0..2])?;
+                let g = parse(&digits[2..4])?;
+                let b = parse(&digits[4..6])?;
+                let a = match digits.get(6..8) {
+                    Some(a) => parse(a)?,
+                    None => 255,
+                };
+                Ok(Self::rgba8(r, g, b, a))
+            }
+            _ => Err(ParseColorError::InvalidHex(hex.to_owned())),
+        }
+    }
+
+    /// Looks up a color by its CSS name, ignoring case.
+    pub fn named(name: &str) -> Option<Self> {
+        Self::NAMED
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, color)| *color)
+    }
+
+    /// Returns this color with a different alpha.
+    pub const fn with_alpha(mut self, alpha: f32) -> Self {
+        self.a = alpha;
+        self
+    }
+
+    /// Converts the color to linear RGBA, which is what shaders and
+    /// sRGB render targets expect. Alpha is unchanged.
+    pub fn to_linear(self) -> [f32; 4] {
+        [
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a,
+        ]
+    }
+
+    /// Creates a color from linear RGBA components.
+    pub fn from_linear([r, g, b, a]: [f32; 4]) -> Self {
+        Self::rgba(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a)
+    }
+
+    /// The sRGB components as an array.
+    pub const fn to_array(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parses a CSS color name or a hex color.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.starts_with('#') {
+            return Self::hex(s);
+        }
+        Self::named(s).ok_or_else(|| ParseColorError::UnknownName(s.to_owned()))
+    }
+}
+
+/// An error returned when parsing a [`Color`] fails.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseColorError {
+    #[error("invalid hex color: {0:?}")]
+    InvalidHex(String),
+    #[error("unknown color name: {0:?}")]
+    UnknownName(String),
+}
+
+/// Converts a gamma encoded sRGB component to linear.
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear component to gamma encoded sRGB.
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Color, b: Color) {
+        let close = a
+            .to_array()
+            .iter()
+            .zip(b.to_array())
+            .all(|(a, b)| (a - b).abs() <= 1.0 / 255.0);
+        assert!(close, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn parse_hex() {
+        assert_eq!(Color::hex("#fff"), Ok(Color::WHITE));
+        assert_eq!(Color::hex("#ff8000"), Ok(Color::rgb8(255, 128, 0)));
+        assert_eq!(Color::hex("ff800080"), Ok(Color::rgba8(255, 128, 0, 128)));
+        assert_eq!(Color::hex("#A0b"), Ok(Color::rgb8(0xaa, 0x00, 0xbb)));
+    }
+
+    #[test]
+    fn reject_invalid_hex() {
+        assert!(Color::hex("#ff").is_err());
+        assert!(Color::hex("#ggg").is_err());
+        assert!(Color::hex("#ff80000").is_err());
+        assert!(Color::hex("#+f+f+f").is_err());
+        assert!(Color::hex("#ffé").is_err());
+    }
+
+    #[test]
+    fn parse_names() {
+        assert_eq!("Orange".parse(), Ok(Color::ORANGE));
+        assert_eq!(" #000 ".parse(), Ok(Color::BLACK));
+        assert_eq!(
+            "beige".parse::<Color>(),
+            Err(ParseColorError::UnknownName("beige".to_owned()))
+        );
+    }
+
+    #[test]
+    fn hsl_to_rgb() {
+        assert_close(Color::hsl(0.0, 1.0, 0.5), Color::RED);
+        assert_close(Color::hsl(120.0, 1.0, 0.25), Color::GREEN);
+        assert_close(Color::hsl(240.0, 1.0, 0.5), Color::BLUE);
+        assert_close(Color::hsl(-60.0, 1.0, 0.5), Color::FUCHSIA);
+        assert_close(Color::hsl(0.0, 0.0, 1.0), Color::WHITE);
+        assert_close(Color::hsl(39.0, 1.0, 0.5), Color::rgb8(255, 166, 0));
+    }
+
+    #[test]
+    fn hsv_to_rgb() {
+        assert_close(Color::hsv(0.0, 1.0, 1.0), Color::RED);
+        assert_close(Color::hsv(180.0, 1.0, 0.5), Color::TEAL);
+        assert_close(Color::hsv(60.0, 0.0, 0.0), Color::BLACK);
+    }
+
+    #[test]
+    fn linear_round_trip() {
+        assert_eq!(Color::WHITE.to_linear(), [1.0; 4]);
+        assert_eq!(Color::BLACK.to_linear(), [0.0, 0.0, 0.0, 1.0]);
+
+        let [r, ..] = Color::GRAY.to_linear();
+        assert!((r - 0.2158).abs() < 1e-3);
+
+        let color = Color::rgba8(12, 128, 200, 100);
+        assert_close(Color::from_linear(color.to_linear()), color);
+    }
+}
@@ -0,0 +1,895 @@
+use std::ops::Range;
+
+use crate::brush::StopData;
+use crate::clip::{Clip, ClipShape};
+use crate::glyph::GlyphKey;
+use crate::texture::TextureKey;
+use crate::{
+    Brush, Color, CornerRadii, Font, ImageData, ImageFit, ImageSampling, Mesh, ParagraphLayout,
+    Position, Rect, RectInstance, RoundedRect, ShapedGlyph, ShapedText, TextStyle, Transform,
+    Vertex,
+};
+
+/// The pipeline that a [`Batch`] is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BatchKind {
+    /// Indexed [`Vertex`] triangles.
+    Mesh,
+    /// Instanced [`RoundedRect`]s drawn with the rounded rect shader.
+    Rect,
+    /// Indexed [`Vertex`] triangles textured with an image.
+    Image(TextureKey),
+    /// Indexed [`Vertex`] triangles textured with images packed into
+    /// an atlas page.
+    AtlasImage(ImageSampling),
+}
+
+/// A range of indices, or of instances for rectangles, that is drawn
+/// with a single draw call.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Batch {
+    pub kind: BatchKind,
+    pub range: Range<u32>,
+    /// The scissor rect in logical pixels, `None` for the whole target.
+    pub scissor: Option<Rect>,
+    /// The index of the shape in [`DrawList::clip_shapes`] that the
+    /// batch is clipped to.
+    pub clip_shape: Option<u32>,
+    /// The atlas page the batch samples, `None` if it can be drawn
+    /// with any page.
+    pub page: Option<u32>,
+}
+
+/// A quad sampling a glyph from the glyph atlas, whose texture
+/// coordinates are filled in once the glyph has been rasterized.
+#[derive(Debug, Clone)]
+pub(crate) struct GlyphQuad {
+    pub font: Font,
+    pub key: GlyphKey,
+    /// The first of the quad's 4 vertices in the mesh.
+    pub first_vertex: u32,
+    /// The first of the quad's 6 indices.
+    pub first_index: u32,
+}
+
+/// A quad sampling an image, whose texture coordinates are moved into
+/// an atlas page if the image is packed into one.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ImageQuad {
+    pub key: TextureKey,
+    pub first_vertex: u32,
+    pub first_index: u32,
+}
+
+/// A quad whose texture was put on an atlas page, see
+/// [`DrawList::assign_pages`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PagedQuad {
+    pub first_index: u32,
+    pub kind: BatchKind,
+    pub page: u32,
+}
+
+/// Geometry queued for drawing.
+///
+/// Each kind of primitive has its own vertex buffer, batches record the
+/// order they were drawn in so that later primitives are drawn on top.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct DrawList {
+    pub mesh: Mesh,
+    pub rects: Vec<RectInstance>,
+    /// The gradient stops of every brush, sorted by offset within
+    /// each brush.
+    pub gradient_stops: Vec<StopData>,
+    pub batches: Vec<Batch>,
+    /// The shapes that batches are clipped to.
+    pub clip_shapes: Vec<ClipShape>,
+    /// The glyphs drawn by the mesh.
+    pub glyphs: Vec<GlyphQuad>,
+    /// The images drawn by the mesh, each listed once.
+    pub images: Vec<(TextureKey, ImageData)>,
+    pub image_quads: Vec<ImageQuad>,
+    /// The transform applied to everything that's pushed.
+    pub transform: Transform,
+    /// The clip applied to everything that's pushed.
+    pub clip: Clip,
+}
+
+impl DrawList {
+    /// Adds vertices to be drawn as a triangle list.
+    pub fn push(&mut self, vertices: &[Vertex]) {
+        self.extend_mesh(BatchKind::Mesh, |mesh| mesh.push_triangles(vertices));
+    }
+
+    /// Adds a quad covering `rect`.
+    ///
+    /// Quads filled with a gradient are added as rectangles, which the
+    /// rect shader fills exactly.
+    pub fn push_quad(&mut self, rect: Rect, fill: impl Into<Brush>) {
+        match fill.into() {
+            Brush::Solid(color) => {
+                self.extend_mesh(BatchKind::Mesh, |mesh| mesh.push_quad(rect, color))
+            }
+            gradient => self.push_rect(&RoundedRect::new(rect, CornerRadii::all(0.0), gradient)),
+        }
+    }
+
+    /// Adds an indexed mesh.
+    pub fn push_mesh(&mut self, mesh: &Mesh) {
+        self.extend_mesh(BatchKind::Mesh, |list| list.extend(mesh));
+    }
+
+    /// Adds a quad covering `rect` that's drawn with the coverage of
+    /// a glyph.
+    pub fn push_glyph(&mut self, font: &Font, key: GlyphKey, rect: Rect, color: Color) {
+        self.glyphs.push(GlyphQuad {
+            font: font.clone(),
+            key,
+            first_vertex: self.mesh.vertices().len() as u32,
+            first_index: self.mesh.indices().len() as u32,
+        });
+        self.push_quad(rect, color);
+    }
+
+    /// Adds a quad covering `rect` textured with the part of `image`
+    /// within `uv`, as `[u0, v0, u1, v1]`.
+    pub fn push_image(
+        &mut self,
+        image: &ImageData,
+        rect: Rect,
+        uv: [f32; 4],
+        sampling: ImageSampling,
+    ) {
+        let key = TextureKey {
+            image: image.id(),
+            sampling,
+        };
+        if !self.images.iter().any(|(other, _)| *other == key) {
+            self.images.push((key, image.clone()));
+        }
+
+        let first_vertex = self.mesh.vertices().len();
+        self.image_quads.push(ImageQuad {
+            key,
+            first_vertex: first_vertex as u32,
+            first_index: self.mesh.indices().len() as u32,
+        });
+        self.extend_mesh(BatchKind::Image(key), |mesh| {
+            mesh.push_quad(rect, Color::WHITE)
+        });
+        let [u0, v0, u1, v1] = uv;
+        let vertices = &mut self.mesh.vertices_mut()[first_vertex..];
+        vertices[0].uv = [u0, v0];
+        vertices[1].uv = [u1, v0];
+        vertices[2].uv = [u0, v1];
+        vertices[3].uv = [u1, v1];
+    }
+
+    /// Adds geometry to the mesh with `f`, transforming the new vertices.
+    fn extend_mesh(&mut self, kind: BatchKind, f: impl FnOnce(&mut Mesh)) {
+        let start = self.mesh.indices().len() as u32;
+        let first_vertex = self.mesh.vertices().len();
+        f(&mut self.mesh);
+
+        if !self.transform.is_identity() {
+            for vertex in &mut self.mesh.vertices_mut()[first_vertex..] {
+                let [x, y] = vertex.position;
+                let position = self.transform.transform_point(Position::new(x, y));
+                vertex.position = [position.x, position.y];
+            }
+        }
+
+        self.add_batch(kind, start..self.mesh.indices().len() as u32);
+    }
+
+    /// Adds a rounded rectangle, and its shadow beneath it.
+    pub fn push_rect(&mut self, rect: &RoundedRect) {
+        let start = self.rects.len() as u32;
+        if let Some(shadow) = rect.shadow_instance() {
+            self.rects.push(shadow.with_transform(self.transform));
+        }
+
+        let first_stop = self.gradient_stops.len();
+        self.gradient_stops
+            .extend(rect.fill.stops().iter().map(StopData::from));
+        self.gradient_stops[first_stop..].sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+        let mut instance = rect.instance().with_transform(self.transform);
+        instance.brush[1] += first_stop as u32;
+        self.rects.push(instance);
+        self.add_batch(BatchKind::Rect, start..self.rects.len() as u32);
+    }
+
+    /// Moves all the geometry from `other` to the end of this list.
+    pub fn append(&mut self, other: &mut DrawList) {
+        let index_offset = self.mesh.indices().len() as u32;
+        let rect_offset = self.rects.len() as u32;
+
+        let shape_offset = self.clip_shapes.len() as u32;
+        let vertex_offset = self.mesh.vertices().len() as u32;
+
+        for batch in other.batches.drain(..) {
+            let offset = match batch.kind {
+                BatchKind::Mesh | BatchKind::Image(_) | BatchKind::AtlasImage(_) => index_offset,
+                BatchKind::Rect => rect_offset,
+            };
+            self.merge_batch(Batch {
+                range: batch.range.start + offset..batch.range.end + offset,
+                clip_shape: batch.clip_shape.map(|shape| shape + shape_offset),
+                ..batch
+            });
+        }
+
+        let stop_offset = self.gradient_stops.len() as u32;
+        for instance in &mut other.rects {
+            instance.brush[1] += stop_offset;
+        }
+
+        self.mesh.extend(&other.mesh);
+        other.mesh.clear();
+        self.rects.append(&mut other.rects);
+        self.gradient_stops.append(&mut other.gradient_stops);
+        self.clip_shapes.append(&mut other.clip_shapes);
+        self.glyphs
+            .extend(other.glyphs.drain(..).map(|glyph| GlyphQuad {
+                first_vertex: glyph.first_vertex + vertex_offset,
+                first_index: glyph.first_index + index_offset,
+                ..glyph
+            }));
+        self.image_quads
+            .extend(other.image_quads.drain(..).map(|quad| ImageQuad {
+                first_vertex: quad.first_vertex + vertex_offset,
+                first_index: quad.first_index + index_offset,
+                ..quad
+            }));
+        for (key, image) in other.images.drain(..) {
+            if !self.images.iter().any(|(other, _)| *other == key) {
+                self.images.push((key, image));
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.mesh.clear();
+        self.rects.clear();
+        self.gradient_stops.clear();
+        self.batches.clear();
+        self.clip_shapes.clear();
+        self.glyphs.clear();
+        self.images.clear();
+        self.image_quads.clear();
+    }
+
+    /// Splits batches so that each quad in `quads` is drawn with the
+    /// atlas page its texture was put on, once the pages are known.
+    ///
+    /// Other geometry in mesh batches doesn't sample an atlas page, so
+    /// it's drawn with the page of the quads next to it.
+    pub fn assign_pages(&mut self, mut quads: Vec<PagedQuad>) {
+        if quads.is_empty() {
+            return;
+        }
+        quads.sort_by_key(|quad| quad.first_index);
+
+        let mut quads = quads.into_iter().peekable();
+        for batch in std::mem::take(&mut self.batches) {
+            if batch.kind == BatchKind::Rect {
+                self.merge_batch(batch);
+                continue;
+            }
+
+            let mut start = batch.range.start;
+            while let Some(quad) = quads.next_if(|quad| quad.first_index < batch.range.end) {
+                let end = quad.first_index + 6;
+                if start < quad.first_index {
+                    self.merge_batch(Batch {
+                        range: start..quad.first_index,
+                        ..batch.clone()
+                    });
+                }
+                self.merge_batch(Batch {
+                    kind: quad.kind,
+                    range: quad.first_index..end,
+                    page: Some(quad.page),
+                    ..batch.clone()
+                });
+                start = end;
+            }
+            if start < batch.range.end {
+                self.merge_batch(Batch {
+                    range: start..batch.range.end,
+                    ..batch
+                });
+            }
+        }
+    }
+
+    /// Adds a batch with the current clip.
+    fn add_batch(&mut self, kind: BatchKind, range: Range<u32>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // Consecutive batches usually share a clip shape, so only the
+        // last one needs checking
+        let clip_shape = self.clip.shape.map(|shape| {
+            if self.clip_shapes.last() != Some(&shape) {
+                self.clip_shapes.push(shape);
+            }
+            self.clip_shapes.len() as u32 - 1
+        });
+
+        self.merge_batch(Batch {
+            kind,
+            range,
+            scissor: self.clip.scissor,
+            clip_shape,
+            page: None,
+        });
+    }
+
+    /// Adds a batch, merging it into the previous batch if they are
+    /// the same kind, contiguous, clipped the same way and can be drawn
+    /// with the same atlas page.
+    fn merge_batch(&mut self, batch: Batch) {
+        if let Some(last) = self.batches.last_mut()
+            && last.kind == batch.kind
+            && last.range.end == batch.range.start
+            && last.scissor == batch.scissor
+            && last.clip_shape == batch.clip_shape
+            && (last.page.is_none() || batch.page.is_none() || last.page == batch.page)
+        {
+            last.range.end = batch.range.end;
+            last.page = last.page.or(batch.page);
+            return;
+        }
+
+        self.batches.push(batch);
+    }
+}
+
+/// Collects the geometry emitted by widgets while drawing.
+#[derive(Debug)]
+pub struct DrawContext {
+    list: DrawList,
+    bounds: Rect,
+    /// The ratio of physical pixels to logical pixels, which text is
+    /// rasterized at.
+    scale_factor: f32,
+    /// The transforms that were replaced by [`DrawContext::push_transform`].
+    transforms: Vec<Transform>,
+    /// The clips that were replaced by [`DrawContext::push_clip`].
+    clips: Vec<Clip>,
+}
+
+impl Default for DrawContext {
+    fn default() -> Self {
+        Self::with_scale_factor(1.0)
+    }
+}
+
+impl DrawContext {
+    /// Creates an empty [`DrawContext`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty [`DrawContext`] for a target with
+    /// `scale_factor` physical pixels per logical pixel, so that text
+    /// is rasterized at the resolution it's displayed at.
+    pub fn with_scale_factor(scale_factor: f32) -> Self {
+        Self {
+            list: DrawList::default(),
+            bounds: Rect::default(),
+            scale_factor,
+            transforms: Vec::new(),
+            clips: Vec::new(),
+        }
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// The bounds of the widget currently being drawn.
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    pub(crate) fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+    }
+
+    /// The transform applied to everything that's drawn.
+    pub fn transform(&self) -> Transform {
+        self.list.transform
+    }
+
+    /// Applies `transform` to everything drawn until the matching
+    /// [`DrawContext::pop_transform`], before the current transform.
+    ///
+    /// Positions are still given in untransformed coordinates, so
+    /// widgets don't need to know how they're transformed.
+    pub fn push_transform(&mut self, transform: Transform) {
+        self.transforms.push(self.list.transform);
+        self.list.transform = transform.then(self.list.transform);
+    }
+
+    /// Restores the transform from before the last
+    /// [`DrawContext::push_transform`].
+    pub fn pop_transform(&mut self) {
+        if let Some(transform) = self.transforms.pop() {
+            self.list.transform = transform;
+        }
+    }
+
+    /// Restricts everything drawn until the matching
+    /// [`DrawContext::pop_clip`] to `rect`, within any clips that were
+    /// already pushed.
+    ///
+    /// `rect` is transformed by the current transform. Clips that stay
+    /// axis-aligned are applied with a scissor rect, so their edges are
+    /// rounded to whole pixels.
+    pub fn push_clip(&mut self, rect: Rect) {
+        self.push_rounded_clip(rect, CornerRadii::default());
+    }
+
+    /// Like [`DrawContext::push_clip`], but with rounded corners, which
+    /// are clipped with antialiased edges by the shaders.
+    ///
+    /// Shapes can only be intersected with clips that were pushed with
+    /// the same transform. Otherwise only the bounds of the outer clip
+    /// are kept.
+    pub fn push_rounded_clip(&mut self, rect: Rect, radii: CornerRadii) {
+        self.clips.push(self.list.clip);
+        self.list.clip = self.list.clip.intersect(rect, radii, self.list.transform);
+    }
+
+    /// Restores the clip from before the last [`DrawContext::push_clip`].
+    pub fn pop_clip(&mut self) {
+        if let Some(clip) = self.clips.pop() {
+            self.list.clip = clip;
+        }
+    }
+
+    /// The bounds of the current clip in logical pixels, or `None` if
+    /// nothing is clipped.
+    ///
+    /// Widgets can skip drawing anything outside these bounds.
+    pub fn clip_bounds(&self) -> Option<Rect> {
+        self.list.clip.scissor
+    }
+
+    /// Adds vertices to be drawn as a triangle list.
+    pub fn push(&mut self, vertices: &[Vertex]) {
+        self.list.push(vertices);
+    }
+
+    /// Draws a quad covering `rect` filled with a solid color or
+    /// a gradient.
+    pub fn quad(&mut self, rect: Rect, fill: impl Into<Brush>) {
+        self.list.push_quad(rect, fill);
+    }
+
+    /// Draws an indexed mesh.
+    pub fn mesh(&mut self, mesh: &Mesh) {
+        self.list.push_mesh(mesh);
+    }
+
+    /// Draws a rectangle, which can have rounded corners, a border,
+    /// an outline and a shadow.
+    pub fn rect(&mut self, rect: &RoundedRect) {
+        self.list.push_rect(rect);
+    }
+
+    /// Draws a rectangle with rounded corners filled with a solid
+    /// color or a gradient.
+    pub fn rounded_rect(&mut self, rect: Rect, radii: CornerRadii, fill: impl Into<Brush>) {
+        self.list.push_rect(&RoundedRect::new(rect, radii, fill));
+    }
+
+    /// Draws a line of text with the top left of the line at `position`.
+    ///
+    /// Glyphs are snapped to the pixel grid before the transform is
+    /// applied, so text stays sharp unless it's rotated or scaled.
+    pub fn text(&mut self, text: &ShapedText, position: Position, color: Color) {
+        let origin = position.translate(0.0, text.metrics().baseline());
+        self.glyphs(text.font(), text.style(), text.glyphs(), origin, color);
+    }
+
+    /// Draws a laid out paragraph with its top left corner at `position`.
+    pub fn paragraph(&mut self, paragraph: &ParagraphLayout, position: Position) {
+        for run in &paragraph.runs {
+            self.glyphs(&run.font, &run.style, &run.glyphs, position, run.color);
+        }
+    }
+
+    /// Draws `glyphs` positioned relative to `origin`.
+    fn glyphs(
+        &mut self,
+        font: &Font,
+        style: &TextStyle,
+        glyphs: &[ShapedGlyph],
+        origin: Position,
+        color: Color,
+    ) {
+        let weight = font.resolve_weight(style.weight);
+        let face = font.face(weight);
+
+        let scale = self.scale_factor;
+        for glyph in glyphs {
+            let position = [(origin.x + glyph.x) * scale, (origin.y + glyph.y) * scale];
+            let placed =
+                GlyphKey::place(font, &face, glyph.id, style.size * scale, weight, position);
+            let Some(placed) = placed else {
+                continue;
+            };
+
+            let [x, y, width, height] = placed.bounds.map(|value| value as f32 / scale);
+            let rect = Rect::new(x, y, width, height);
+            self.list.push_glyph(font, placed.key, rect, color);
+        }
+    }
+
+    /// Draws `image` in `rect`, sized according to `fit`.
+    pub fn image(&mut self, image: &ImageData, rect: Rect, fit: ImageFit, sampling: ImageSampling) {
+        if let Some((rect, uv)) = fit.place(image.size(), rect) {
+            self.list.push_image(image, rect, uv, sampling);
+        }
+    }
+
+    /// The plain vertices drawn so far, not including rounded rectangles.
+    pub fn vertices(&self) -> &[Vertex] {
+        self.list.mesh.vertices()
+    }
+
+    /// Removes and returns everything drawn so far.
+    pub(crate) fn take_list(&mut self) -> DrawList {
+        let (transform, clip) = (self.list.transform, self.list.clip);
+        let list = std::mem::take(&mut self.list);
+        self.list.transform = transform;
+        self.list.clip = clip;
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unclipped(kind: BatchKind, range: Range<u32>) -> Batch {
+        Batch {
+            kind,
+            range,
+            scissor: None,
+            clip_shape: None,
+            page: None,
+        }
+    }
+
+    #[test]
+    fn merge_contiguous_batches() {
+        let mut list = DrawList::default();
+        list.push_quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+        list.push_quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+        list.push_rect(&RoundedRect::default());
+        list.push_quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+
+        assert_eq!(
+            list.batches,
+            [
+                unclipped(BatchKind::Mesh, 0..12),
+                unclipped(BatchKind::Rect, 0..1),
+                unclipped(BatchKind::Mesh, 12..18),
+            ]
+        );
+    }
+
+    #[test]
+    fn triangle_lists_share_batches_with_quads() {
+        let mut list = DrawList::default();
+        list.push(&Vertex::quad(10.0, 10.0, 0.0, 0.0, Color::BLACK));
+        list.push_quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+
+        assert_eq!(list.mesh.vertices().len(), 10);
+        assert_eq!(list.batches, [unclipped(BatchKind::Mesh, 0..12)]);
+    }
+
+    #[test]
+    fn nested_transforms() {
+        let mut ctx = DrawContext::new();
+        ctx.push_transform(Transform::translate(100.0, 0.0));
+        ctx.push_transform(Transform::scale(2.0, 2.0));
+        ctx.quad(Rect::new(10.0, 10.0, 10.0, 10.0), Color::BLACK);
+        ctx.rect(&RoundedRect::default());
+        ctx.pop_transform();
+        ctx.quad(Rect::new(10.0, 10.0, 10.0, 10.0), Color::BLACK);
+        ctx.pop_transform();
+        ctx.quad(Rect::new(10.0, 10.0, 10.0, 10.0), Color::BLACK);
+
+        let positions: Vec<_> = ctx
+            .vertices()
+            .iter()
+            .step_by(4)
+            .map(|v| v.position)
+            .collect();
+        assert_eq!(positions, [[120.0, 20.0], [110.0, 10.0], [10.0, 10.0]]);
+        assert_eq!(ctx.transform(), Transform::IDENTITY);
+
+        let list = ctx.take_list();
+        assert_eq!(list.rects[0].transform, [2.0, 0.0, 0.0, 2.0]);
+        assert_eq!(list.rects[0].translation, [100.0, 0.0]);
+    }
+
+    #[test]
+    fn split_batches_when_clip_changes() {
+        let quad = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let clip = Rect::new(0.0, 0.0, 50.0, 50.0);
+
+        let mut ctx = DrawContext::new();
+        ctx.quad(quad, Color::BLACK);
+        // Pushing and popping a clip without drawing doesn't split
+        ctx.push_clip(clip);
+        ctx.pop_clip();
+        ctx.quad(quad, Color::BLACK);
+        ctx.push_clip(clip);
+        ctx.quad(quad, Color::BLACK);
+        // A clip that contains the current one doesn't change anything
+        ctx.push_clip(Rect::new(-10.0, -10.0, 100.0, 100.0));
+        ctx.quad(quad, Color::BLACK);
+        ctx.pop_clip();
+        ctx.push_rounded_clip(clip, CornerRadii::all(4.0));
+        ctx.quad(quad, Color::BLACK);
+        ctx.rect(&RoundedRect::default());
+        ctx.pop_clip();
+        ctx.push_rounded_clip(clip, CornerRadii::all(4.0));
+        ctx.quad(quad, Color::BLACK);
+        ctx.pop_clip();
+        ctx.pop_clip();
+        ctx.quad(quad, Color::BLACK);
+
+        let list = ctx.take_list();
+        let clips: Vec<_> = list
+            .batches
+            .iter()
+            .map(|batch| {
+                (
+                    batch.kind,
+                    batch.range.clone(),
+                    batch.scissor,
+                    batch.clip_shape,
+                )
+            })
+            .collect();
+        assert_eq!(
+            clips,
+            [
+                (BatchKind::Mesh, 0..12, None, None),
+                (BatchKind::Mesh, 12..24, Some(clip), None),
+                (BatchKind::Mesh, 24..30, Some(clip), Some(0)),
+                (BatchKind::Rect, 0..1, Some(clip), Some(0)),
+                (BatchKind::Mesh, 30..36, Some(clip), Some(0)),
+                (BatchKind::Mesh, 36..42, None, None),
+            ]
+        );
+        assert_eq!(list.clip_shapes.len(), 1);
+        assert_eq!(ctx.clip_bounds(), None);
+    }
+
+    #[test]
+    fn append_offsets_clip_shapes() {
+        let mut ctx = DrawContext::new();
+        ctx.push_rounded_clip(Rect::new(0.0, 0.0, 50.0, 50.0), CornerRadii::all(4.0));
+        ctx.quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+
+        let mut list = ctx.take_list();
+        ctx.quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+        list.append(&mut ctx.take_list());
+
+        assert_eq!(list.clip_shapes.len(), 2);
+        assert_eq!(list.batches[0].clip_shape, Some(0));
+        assert_eq!(list.batches[1].clip_shape, Some(1));
+    }
+
+    #[test]
+    fn text_quads_on_pixel_grid() {
+        let fonts = crate::text::tests::fonts();
+        let text = fonts.shape("Hi there", &crate::TextStyle::new(16.0));
+
+        let mut ctx = DrawContext::with_scale_factor(2.0);
+        ctx.quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+        ctx.text(&text, Position::new(10.3, 20.0), Color::BLACK);
+
+        let list = ctx.take_list();
+        // The space has no quad
+        assert_eq!(list.glyphs.len(), 7);
+        assert_eq!(list.glyphs[0].first_vertex, 4);
+        assert_eq!(list.batches.len(), 1);
+        for vertex in &list.mesh.vertices()[4..] {
+            let [x, y] = vertex.position.map(|value| value * 2.0);
+            assert_eq!([x, y], [x.round(), y.round()]);
+        }
+    }
+
+    #[test]
+    fn batch_images_by_texture() {
+        let red = ImageData::from_rgba(image::RgbaImage::from_pixel(
+            2,
+            2,
+            image::Rgba([255, 0, 0, 255]),
+        ));
+        let blue = ImageData::from_rgba(image::RgbaImage::from_pixel(
+            2,
+            2,
+            image::Rgba([0, 0, 255, 255]),
+        ));
+        let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
+
+        let mut ctx = DrawContext::new();
+        ctx.image(&red, rect, ImageFit::Fill, ImageSampling::Linear);
+        ctx.image(&red, rect, ImageFit::Fill, ImageSampling::Linear);
+        ctx.image(&blue, rect, ImageFit::Fill, ImageSampling::Linear);
+        ctx.image(&red, rect, ImageFit::Fill, ImageSampling::Nearest);
+
+        let mut list = DrawList::default();
+        list.push_image(&red, rect, [0.0, 0.0, 1.0, 1.0], ImageSampling::Linear);
+        list.append(&mut ctx.take_list());
+
+        let key = |image: &ImageData, sampling| {
+            BatchKind::Image(TextureKey {
+                image: image.id(),
+                sampling,
+            })
+        };
+        assert_eq!(
+            list.batches,
+            [
+                unclipped(key(&red, ImageSampling::Linear), 0..18),
+                unclipped(key(&blue, ImageSampling::Linear), 18..24),
+                unclipped(key(&red, ImageSampling::Nearest), 24..30),
+            ]
+        );
+        assert_eq!(list.images.len(), 3);
+    }
+
+    #[test]
+    fn split_batches_by_atlas_page() {
+        let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let mut ctx = DrawContext::new();
+        for _ in 0..5 {
+            ctx.quad(rect, Color::BLACK);
+        }
+
+        let mut list = ctx.take_list();
+        let quad = |first_index, page| PagedQuad {
+            first_index,
+            kind: BatchKind::Mesh,
+            page,
+        };
+        list.assign_pages(vec![quad(18, 1), quad(6, 0), quad(24, 1)]);
+
+        // Quads that aren't glyphs join the page next to them
+        let paged = |range, page| Batch {
+            page: Some(page),
+            ..unclipped(BatchKind::Mesh, range)
+        };
+        assert_eq!(list.batches, [paged(0..18, 0), paged(18..30, 1)]);
+    }
+
+    #[test]
+    fn merge_images_on_the_same_page() {
+        let red = ImageData::from_rgba(image::RgbaImage::from_pixel(
+            2,
+            2,
+            image::Rgba([255, 0, 0, 255]),
+        ));
+        let blue = ImageData::from_rgba(image::RgbaImage::from_pixel(
+            2,
+            2,
+            image::Rgba([0, 0, 255, 255]),
+        ));
+        let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
+
+        let mut ctx = DrawContext::new();
+        ctx.image(&red, rect, ImageFit::Fill, ImageSampling::Linear);
+        ctx.image(&blue, rect, ImageFit::Fill, ImageSampling::Linear);
+        let mut list = ctx.take_list();
+        assert_eq!(list.batches.len(), 2);
+
+        let kind = BatchKind::AtlasImage(ImageSampling::Linear);
+        let quads = list
+            .image_quads
+            .iter()
+            .map(|quad| PagedQuad {
+                first_index: quad.first_index,
+                kind,
+                page: 0,
+            })
+            .collect();
+        list.assign_pages(quads);
+
+        let batch = Batch {
+            page: Some(0),
+            ..unclipped(kind, 0..12)
+        };
+        assert_eq!(list.batches, [batch]);
+    }
+
+    #[test]
+    fn sorts_and_offsets_gradient_stops() {
+        use crate::LinearGradient;
+
+        let gradient = LinearGradient::new(0.0)
+            .stop(1.0, Color::BLUE)
+            .stop(0.0, Color::RED);
+        let rect = RoundedRect::new(
+            Rect::new(0.0, 0.0, 10.0, 10.0),
+            CornerRadii::all(0.0),
+            gradient,
+        );
+
+        let mut list = DrawList::default();
+        list.push_rect(&rect);
+
+        let mut other = DrawList::default();
+        other.push_rect(&rect);
+        list.append(&mut other);
+
+        assert_eq!(list.gradient_stops.len(), 4);
+        assert_eq!(list.gradient_stops[0].offset, 0.0);
+        assert_eq!(list.gradient_stops[1].offset, 1.0);
+        assert_eq!(list.rects[0].brush[1..3], [0, 2]);
+        assert_eq!(list.rects[1].brush[1..3], [2, 2]);
+    }
+
+    #[test]
+    fn gradient_quads_are_drawn_as_rects() {
+        use crate::{LinearGradient, brush::BrushParams};
+
+        let gradient = LinearGradient::new(0.0)
+            .stop(0.0, Color::RED)
+            .stop(1.0, Color::BLUE);
+        let mut ctx = DrawContext::new();
+        ctx.quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+        ctx.quad(Rect::new(10.0, 0.0, 20.0, 10.0), gradient);
+
+        let list = ctx.take_list();
+        assert_eq!(list.mesh.vertices().len(), 4);
+        assert_eq!(list.rects.len(), 1);
+        assert_eq!(list.rects[0].rect, [10.0, 0.0, 20.0, 10.0]);
+        assert_eq!(list.rects[0].brush[..3], [BrushParams::LINEAR, 0, 2]);
+        assert_eq!(list.gradient_stops.len(), 2);
+        assert_eq!(
+            list.batches,
+            [
+                unclipped(BatchKind::Mesh, 0..6),
+                unclipped(BatchKind::Rect, 0..1),
+            ]
+        );
+    }
+
+    #[test]
+    fn append_offsets_batches() {
+        let mut list = DrawList::default();
+        list.push_quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+        list.push_rect(&RoundedRect::default());
+
+        let mut other = DrawList::default();
+        other.push_rect(&RoundedRect::default());
+        other.push_quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+        list.append(&mut other);
+
+        assert!(other.batches.is_empty());
+        assert!(other.mesh.is_empty());
+        assert_eq!(list.mesh.vertices().len(), 8);
+        assert_eq!(list.rects.len(), 2);
+        assert_eq!(
+            list.batches,
+            [
+                unclipped(BatchKind::Mesh, 0..6),
+                unclipped(BatchKind::Rect, 0..2),
+                unclipped(BatchKind::Mesh, 6..12),
+            ]
+        );
+    }
+}
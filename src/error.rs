@@ -0,0 +1,50 @@
+/// The errors that can occur while running an [`App`](crate::App).
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// No GPU adapter compatible with the surface was found.
+    #[error("No suitable GPU adapter was found: {0}")]
+    NoAdapter(#[from] wgpu::RequestAdapterError),
+    /// The adapter can't present to the window's surface, as it supports
+    /// no texture formats, present modes or alpha modes for it.
+    #[error("The GPU adapter can't present to the window")]
+    IncompatibleSurface,
+    /// The adapter was found but a device could not be created from it.
+    #[error("Failed to request a GPU device: {0}")]
+    RequestDevice(#[from] wgpu::RequestDeviceError),
+    /// A surface could not be created for the window.
+    #[error("Failed to create a surface: {0}")]
+    CreateSurface(#[from] wgpu::CreateSurfaceError),
+    /// The operating system failed to create a window.
+    #[error("Failed to create a window: {0}")]
+    CreateWindow(#[from] winit::error::OsError),
+    /// The event loop could not be created or exited with an error.
+    #[error("Event loop error: {0}")]
+    EventLoop(#[from] winit::error::EventLoopError),
+    /// The window icon could not be loaded.
+    #[error("Failed to load the window icon: {0}")]
+    LoadIcon(image::ImageError),
+    /// The window icon was loaded but isn't a valid icon.
+    #[error("Invalid window icon: {0}")]
+    BadIcon(#[from] winit::window::BadIcon),
+    /// The GPU ran out of memory while acquiring a frame.
+    #[error("The GPU ran out of memory while acquiring the next frame")]
+    OutOfMemory,
+    /// Frames rendered to a window can't be read back, only headless
+    /// frames can.
+    #[error("Only frames rendered by a headless state can be captured")]
+    CaptureWindow,
+    /// A window has widgets but no fonts to lay them out with, which
+    /// happens when the `bundled-font` feature is disabled and no fonts
+    /// were given with [`WindowConfig::fonts`].
+    ///
+    /// [`WindowConfig::fonts`]: crate::WindowConfig::fonts
+    #[error("The window has widgets but no fonts were set")]
+    MissingFonts,
+    /// The frame couldn't be mapped to be read back from the GPU.
+    #[error("Failed to read the frame back from the GPU: {0}")]
+    Capture(#[from] wgpu::BufferAsyncError),
+    /// Waiting for the GPU to finish failed, usually because the
+    /// device was lost.
+    #[error("Failed to wait for the GPU: {0}")]
+    Poll(#[from] wgpu::PollError),
+}
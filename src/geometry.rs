@@ -0,0 +1,113 @@
+/// The width and height of something, in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Size {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Size {
+    pub const ZERO: Self = Self::new(0.0, 0.0);
+
+    /// Creates a new [`Size`].
+    pub const fn new(width: f32, height: f32) -> Self {
+        Self { width, height }
+    }
+
+    /// Creates a [`Size`] with the same width and height.
+    pub const fn splat(value: f32) -> Self {
+        Self::new(value, value)
+    }
+}
+
+/// A point in 2D space, in logical pixels, with the origin at the
+/// top left.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Position {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Position {
+    pub const ZERO: Self = Self::new(0.0, 0.0);
+
+    /// Creates a new [`Position`].
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    /// Returns this position moved by `dx` and `dy`.
+    pub const fn translate(self, dx: f32, dy: f32) -> Self {
+        Self::new(self.x + dx, self.y + dy)
+    }
+}
+
+/// An axis-aligned rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Rect {
+    pub position: Position,
+    pub size: Size,
+}
+
+impl Rect {
+    /// Creates a new [`Rect`].
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            position: Position::new(x, y),
+            size: Size::new(width, height),
+        }
+    }
+
+    /// Creates a [`Rect`] from its position and size.
+    pub const fn from_parts(position: Position, size: Size) -> Self {
+        Self { position, size }
+    }
+
+    pub const fn x(&self) -> f32 {
+        self.position.x
+    }
+
+    pub const fn y(&self) -> f32 {
+        self.position.y
+    }
+
+    pub const fn width(&self) -> f32 {
+        self.size.width
+    }
+
+    pub const fn height(&self) -> f32 {
+        self.size.height
+    }
+
+    /// The x coordinate of the right edge.
+    pub const fn right(&self) -> f32 {
+        self.position.x + self.size.width
+    }
+
+    /// The y coordinate of the bottom edge.
+    pub const fn bottom(&self) -> f32 {
+        self.position.y + self.size.height
+    }
+
+    /// Returns `true` if the point is inside the rectangle.
+    pub fn contains(&self, point: Position) -> bool {
+        point.x >= self.x()
+            && point.x < self.right()
+            && point.y >= self.y()
+            && point.y < self.bottom()
+    }
+
+    /// The area covered by both rectangles, which is empty if they
+    /// don't overlap.
+    pub fn intersection(&self, other: Rect) -> Rect {
+        let x = self.x().max(other.x());
+        let y = self.y().max(other.y());
+        let right = self.right().min(other.right()).max(x);
+        let bottom = self.bottom().min(other.bottom()).max(y);
+        Rect::new(x, y, right - x, bottom - y)
+    }
+
+    /// Returns `true` if the rectangle has no area.
+    pub fn is_empty(&self) -> bool {
+        self.width() <= 0.0 || self.height() <= 0.0
+    }
+}
@@ -0,0 +1,71 @@
+use bytemuck::{Pod, Zeroable};
+use winit::dpi::PhysicalSize;
+
+/// The uniforms shared by every shader.
+///
+/// Geometry is drawn in logical pixels with the origin at the top left
+/// of the render target, the projection maps it to clip space.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub(crate) struct Globals {
+    /// A column major orthographic projection from logical pixels to
+    /// clip space.
+    pub projection: [[f32; 4]; 4],
+    /// The ratio of physical pixels to logical pixels.
+    pub scale_factor: f32,
+    _padding: [f32; 3],
+}
+
+impl Globals {
+    /// Creates the globals for a render target of `size` physical pixels.
+    pub fn new(size: PhysicalSize<u32>, scale_factor: f64) -> Self {
+        let logical = size.to_logical::<f32>(scale_factor);
+        Self {
+            projection: orthographic(logical.width, logical.height),
+            scale_factor: scale_factor as f32,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+/// A projection mapping `(0, 0)` to the top left of clip space and
+/// `(width, height)` to the bottom right.
+fn orthographic(width: f32, height: f32) -> [[f32; 4]; 4] {
+    let (width, height) = (width.max(1.0), height.max(1.0));
+    [
+        [2.0 / width, 0.0, 0.0, 0.0],
+        [0.0, -2.0 / height, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [-1.0, 1.0, 0.0, 1.0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(globals: &Globals, [x, y]: [f32; 2]) -> [f32; 2] {
+        let m = globals.projection;
+        [
+            m[0][0] * x + m[1][0] * y + m[3][0],
+            m[0][1] * x + m[1][1] * y + m[3][1],
+        ]
+    }
+
+    #[test]
+    fn top_left_origin() {
+        let globals = Globals::new(PhysicalSize::new(800, 600), 1.0);
+
+        assert_eq!(project(&globals, [0.0, 0.0]), [-1.0, 1.0]);
+        assert_eq!(project(&globals, [800.0, 600.0]), [1.0, -1.0]);
+        assert_eq!(project(&globals, [400.0, 300.0]), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn logical_pixels() {
+        let globals = Globals::new(PhysicalSize::new(800, 600), 2.0);
+
+        assert_eq!(globals.scale_factor, 2.0);
+        assert_eq!(project(&globals, [400.0, 300.0]), [1.0, -1.0]);
+    }
+}
@@ -0,0 +1,478 @@
+use ab_glyph_rasterizer::{Point, Rasterizer, point};
+use rustybuzz::ttf_parser::{Face, GlyphId, OutlineBuilder};
+use wgpu::{
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Device,
+    Extent3d, FilterMode, Origin3d, Queue, Sampler, SamplerBindingType, SamplerDescriptor,
+    ShaderStages, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture, TextureAspect,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+    TextureViewDimension,
+};
+
+use crate::atlas::{Allocation, AtlasAllocator};
+use crate::draw::{BatchKind, DrawList, PagedQuad};
+use crate::{Font, FontWeight};
+
+/// The number of horizontal positions within a pixel that glyphs are
+/// rasterized at, so that text is spaced evenly.
+const SUBPIXEL_STEPS: f32 = 4.0;
+
+/// Identifies a glyph rasterized at a size and subpixel offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct GlyphKey {
+    font: u64,
+    glyph: u16,
+    /// The font size in physical pixels, as bits so the key can be hashed.
+    size: u32,
+    weight: FontWeight,
+    /// The horizontal offset of the origin within a pixel, in steps
+    /// of `1 / SUBPIXEL_STEPS`.
+    subpixel: u8,
+}
+
+/// A glyph placed on the pixel grid, ready to be drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PlacedGlyph {
+    pub key: GlyphKey,
+    /// The bitmap bounds in physical pixels, as `[x, y, width, height]`.
+    pub bounds: [i32; 4],
+}
+
+impl GlyphKey {
+    /// Places `glyph` with its origin at `origin`, in physical pixels.
+    ///
+    /// `face` is the font parsed at `weight`, as returned by
+    /// [`Font::face`]. The origin is snapped to a whole pixel vertically
+    /// and to a subpixel step horizontally. Returns `None` for glyphs
+    /// without an outline, such as spaces.
+    pub fn place(
+        font: &Font,
+        face: &Face,
+        glyph: u16,
+        size: f32,
+        weight: FontWeight,
+        origin: [f32; 2],
+    ) -> Option<PlacedGlyph> {
+        let x = (origin[0] * SUBPIXEL_STEPS).round() / SUBPIXEL_STEPS;
+        let (pixel_x, pixel_y) = (x.floor(), origin[1].round());
+
+        let key = Self {
+            font: font.id(),
+            glyph,
+            size: size.to_bits(),
+            weight,
+            subpixel: ((x - pixel_x) * SUBPIXEL_STEPS) as u8,
+        };
+
+        let [left, top, width, height] = key.bounds(face)?;
+        Some(PlacedGlyph {
+            key,
+            bounds: [pixel_x as i32 + left, pixel_y as i32 + top, width, height],
+        })
+    }
+
+    fn scale(&self, face: &Face) -> f32 {
+        f32::from_bits(self.size) / face.units_per_em() as f32
+    }
+
+    fn offset(&self) -> f32 {
+        self.subpixel as f32 / SUBPIXEL_STEPS
+    }
+
+    /// The bounds of the glyph's bitmap relative to its origin, in
+    /// physical pixels, as `[left, top, width, height]`.
+    fn bounds(&self, face: &Face) -> Option<[i32; 4]> {
+        let id = GlyphId(self.glyph);
+        // Bounding boxes in the font don't account for variations
+        let rect = if face.is_variable() {
+            face.outline_glyph(id, &mut NoOutline)?
+        } else {
+            face.glyph_bounding_box(id)?
+        };
+
+        let scale = self.scale(face);
+        let left = (rect.x_min as f32 * scale + self.offset()).floor() as i32;
+        let right = (rect.x_max as f32 * scale + self.offset()).ceil() as i32;
+        let top = (-rect.y_max as f32 * scale).floor() as i32;
+        let bottom = (-rect.y_min as f32 * scale).ceil() as i32;
+
+        (right > left && bottom > top).then(|| [left, top, right - left, bottom - top])
+    }
+
+    /// Rasterizes the glyph into a coverage bitmap covering
+    /// [`GlyphKey::bounds`], one byte per pixel.
+    fn rasterize(&self, face: &Face) -> Option<([i32; 4], Vec<u8>)> {
+        let bounds = self.bounds(face)?;
+        let [left, top, width, height] = bounds;
+
+        let scale = self.scale(face);
+        let mut builder = RasterBuilder {
+            rasterizer: Rasterizer::new(width as usize, height as usize),
+            transform: (scale, self.offset() - left as f32, -top as f32),
+            start: point(0.0, 0.0),
+            last: point(0.0, 0.0),
+        };
+        face.outline_glyph(GlyphId(self.glyph), &mut builder)?;
+
+        let mut pixels = vec![0; (width * height) as usize];
+        builder.rasterizer.for_each_pixel(|i, coverage| {
+            pixels[i] = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+        });
+        Some((bounds, pixels))
+    }
+}
+
+/// Ignores an outline, for its bounding box.
+struct NoOutline;
+
+impl OutlineBuilder for NoOutline {
+    fn move_to(&mut self, _: f32, _: f32) {}
+    fn line_to(&mut self, _: f32, _: f32) {}
+    fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {}
+    fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {}
+    fn close(&mut self) {}
+}
+
+/// Draws an outline, in font units with y pointing up, into a bitmap.
+struct RasterBuilder {
+    rasterizer: Rasterizer,
+    /// The scale, and the offset after scaling, which also flips y.
+    transform: (f32, f32, f32),
+    start: Point,
+    last: Point,
+}
+
+impl RasterBuilder {
+    fn point(&self, x: f32, y: f32) -> Point {
+        let (scale, dx, dy) = self.transform;
+        point(x * scale + dx, -y * scale + dy)
+    }
+}
+
+impl OutlineBuilder for RasterBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = self.point(x, y);
+        self.last = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.rasterizer.draw_line(self.last, p);
+        self.last = p;
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (c, p) = (self.point(x1, y1), self.point(x, y));
+        self.rasterizer.draw_quad(self.last, c, p);
+        self.last = p;
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (c1, c2, p) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        self.rasterizer.draw_cubic(self.last, c1, c2, p);
+        self.last = p;
+    }
+
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.rasterizer.draw_line(self.last, self.start);
+        }
+        self.last = self.start;
+    }
+}
+
+/// Textures holding rasterized glyphs, which text quads sample their
+/// coverage from.
+///
+/// Glyphs are packed into pages by an [`AtlasAllocator`], which adds
+/// pages as needed and evicts the glyphs that were drawn least recently
+/// once the last page is full. The bottom right corner of every page is
+/// opaque, so vertices with the default `uv` of `[1.0, 1.0]` are drawn
+/// in their plain color whichever page is bound.
+#[derive(Debug)]
+pub(crate) struct GlyphAtlas {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    pages: Vec<AtlasPage>,
+    allocator: AtlasAllocator<GlyphKey>,
+}
+
+/// The texture of a page and the bind group sampling it.
+#[derive(Debug)]
+struct AtlasPage {
+    texture: Texture,
+    bind_group: BindGroup,
+}
+
+impl GlyphAtlas {
+    /// The width and height of each page.
+    const SIZE: u32 = 1024;
+
+    /// The most pages the atlas grows to before evicting glyphs.
+    const MAX_PAGES: usize = 4;
+
+    /// The size of the opaque block in the bottom right corner.
+    const OPAQUE: u32 = 2;
+
+    /// The gap between glyphs, so they don't bleed into each other
+    /// when sampled between pixels.
+    const PADDING: u32 = 1;
+
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let layout = Self::create_layout(device);
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Glyph atlas sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        // The opaque corner is kept out of the rows
+        let size = [Self::SIZE, Self::SIZE - Self::OPAQUE];
+        let mut atlas = Self {
+            layout,
+            sampler,
+            pages: Vec::new(),
+            allocator: AtlasAllocator::new(size, Self::PADDING, Self::MAX_PAGES),
+        };
+        // Geometry that isn't text needs a page to bind from the start
+        atlas.add_page(device, queue);
+        atlas
+    }
+
+    fn add_page(&mut self, device: &Device, queue: &Queue) {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Glyph atlas"),
+            size: Extent3d {
+                width: Self::SIZE,
+                height: Self::SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::R8Unorm,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let corner = Self::SIZE - Self::OPAQUE;
+        let opaque = [u8::MAX; (Self::OPAQUE * Self::OPAQUE) as usize];
+        write_texture(
+            queue,
+            &texture,
+            [corner, corner, Self::OPAQUE, Self::OPAQUE],
+            &opaque,
+        );
+
+        let view = texture.create_view(&Default::default());
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Glyph atlas bind group"),
+            layout: &self.layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+
+        self.pages.push(AtlasPage {
+            texture,
+            bind_group,
+        });
+    }
+
+    fn create_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Glyph atlas layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    pub fn layout(&self) -> &BindGroupLayout {
+        &self.layout
+    }
+
+    /// The bind group of `page`, or of the first page for `None`.
+    pub fn bind_group(&self, page: Option<u32>) -> &BindGroup {
+        &self.pages[page.unwrap_or(0) as usize].bind_group
+    }
+
+    /// Rasterizes the glyphs in `list` that aren't in the atlas yet and
+    /// points the texture coordinates of their quads at them, returning
+    /// the page of each quad.
+    ///
+    /// Glyphs that can't be rasterized or don't fit are left out.
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        list: &mut DrawList,
+    ) -> Vec<PagedQuad> {
+        self.allocator.next_frame();
+
+        let mut quads = Vec::with_capacity(list.glyphs.len());
+        for glyph in &list.glyphs {
+            let allocation = match self.allocator.get(&glyph.key) {
+                Some(allocation) => Some(allocation),
+                None => self.insert(device, queue, &glyph.font, glyph.key),
+            };
+
+            let first = glyph.first_vertex as usize;
+            let vertices = &mut list.mesh.vertices_mut()[first..first + 4];
+            let Some(Allocation { page, rect }) = allocation else {
+                // Collapse the quad so nothing is drawn
+                let position = vertices[0].position;
+                vertices
+                    .iter_mut()
+                    .for_each(|vertex| vertex.position = position);
+                continue;
+            };
+
+            let [x, y, width, height] = rect;
+            let [u0, v0, u1, v1] =
+                [x, y, x + width, y + height].map(|value| value as f32 / Self::SIZE as f32);
+            vertices[0].uv = [u0, v0];
+            vertices[1].uv = [u1, v0];
+            vertices[2].uv = [u0, v1];
+            vertices[3].uv = [u1, v1];
+            quads.push(PagedQuad {
+                first_index: glyph.first_index,
+                kind: BatchKind::Mesh,
+                page: page as u32,
+            });
+        }
+        quads
+    }
+
+    /// Rasterizes a glyph into the atlas, returning where it was put.
+    fn insert(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        font: &Font,
+        key: GlyphKey,
+    ) -> Option<Allocation> {
+        let (bounds, pixels) = key.rasterize(&font.face(key.weight))?;
+        let (width, height) = (bounds[2] as u32, bounds[3] as u32);
+
+        let Some(allocation) = self.allocator.insert(key, width, height) else {
+            log::warn!("the glyph atlas is full, some text will be missing for a frame");
+            return None;
+        };
+        while self.pages.len() < self.allocator.pages() {
+            self.add_page(device, queue);
+        }
+
+        // The padding is cleared too, in case an evicted glyph was there
+        let (padded_width, padded_height) = (width + Self::PADDING, height + Self::PADDING);
+        let mut padded = vec![0; (padded_width * padded_height) as usize];
+        for (row, pixels) in padded
+            .chunks_mut(padded_width as usize)
+            .zip(pixels.chunks(width as usize))
+        {
+            row[..width as usize].copy_from_slice(pixels);
+        }
+        let [x, y, ..] = allocation.rect;
+        let texture = &self.pages[allocation.page].texture;
+        write_texture(queue, texture, [x, y, padded_width, padded_height], &padded);
+        Some(allocation)
+    }
+}
+
+fn write_texture(queue: &Queue, texture: &Texture, [x, y, width, height]: [u32; 4], pixels: &[u8]) {
+    queue.write_texture(
+        TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: Origin3d { x, y, z: 0 },
+            aspect: TextureAspect::All,
+        },
+        pixels,
+        TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(width),
+            rows_per_image: Some(height),
+        },
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::tests::fonts;
+
+    #[test]
+    fn place_on_pixel_grid() {
+        let font = fonts().fonts()[0].clone();
+        let face = font.face(FontWeight::NORMAL);
+        let place =
+            |glyph, origin| GlyphKey::place(&font, &face, glyph, 16.0, FontWeight::NORMAL, origin);
+        let glyph = face.glyph_index('H').unwrap().0;
+
+        let a = place(glyph, [10.0, 20.4]).unwrap();
+        let b = place(glyph, [30.02, 40.0]).unwrap();
+        let c = place(glyph, [10.5, 20.0]).unwrap();
+
+        // Whole pixel moves reuse the same bitmap
+        assert_eq!(a.key, b.key);
+        assert_eq!(b.bounds[0] - a.bounds[0], 20);
+        assert_eq!(b.bounds[1] - a.bounds[1], 20);
+        assert_eq!(a.bounds[2..], b.bounds[2..]);
+        // Half a pixel is a different subpixel offset
+        assert_ne!(a.key, c.key);
+        // The baseline is at the bottom of the letter
+        assert_eq!(a.bounds[1] + a.bounds[3], 20);
+
+        let space = face.glyph_index(' ').unwrap().0;
+        assert_eq!(place(space, [0.0, 0.0]), None);
+    }
+
+    #[test]
+    fn rasterize_coverage() {
+        let font = fonts().fonts()[0].clone();
+        let face = font.face(FontWeight::NORMAL);
+        let glyph = face.glyph_index('l').unwrap().0;
+        let placed =
+            GlyphKey::place(&font, &face, glyph, 32.0, FontWeight::NORMAL, [0.0, 0.0]).unwrap();
+
+        let (bounds, pixels) = placed.key.rasterize(&face).unwrap();
+        let [_, _, width, height] = bounds;
+        assert_eq!(pixels.len(), (width * height) as usize);
+
+        // A vertical stem is solid in the middle row and fades at
+        // the edges
+        let row = &pixels[(height / 2 * width) as usize..][..width as usize];
+        assert!(row.contains(&255), "{row:?}");
+        assert!(row.iter().any(|&coverage| coverage < 255));
+    }
+}
@@ -0,0 +1,111 @@
+mod flex;
+mod sizing;
+
+pub use flex::{Axis, CrossAxisAlignment, Flex, FlexItem, MainAxisAlignment, Padding};
+pub use sizing::{AxisSizing, BoxSizing, Sizing};
+
+use crate::{Fonts, Position, Size, widget::WidgetNode};
+
+/// The minimum and maximum size that a widget can be.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Constraints {
+    pub min: Size,
+    pub max: Size,
+}
+
+impl Constraints {
+    /// Creates new [`Constraints`].
+    pub const fn new(min: Size, max: Size) -> Self {
+        Self { min, max }
+    }
+
+    /// Constraints that only allow exactly one size.
+    pub const fn tight(size: Size) -> Self {
+        Self::new(size, size)
+    }
+
+    /// Constraints that allow any size up to `max`.
+    pub const fn loose(max: Size) -> Self {
+        Self::new(Size::ZERO, max)
+    }
+
+    /// Constraints with no upper bound.
+    pub const fn unbounded() -> Self {
+        Self::new(Size::ZERO, Size::splat(f32::INFINITY))
+    }
+
+    /// Clamps `size` so that it satisfies these constraints.
+    pub fn constrain(&self, size: Size) -> Size {
+        Size::new(
+            size.width.clamp(self.min.width, self.max.width),
+            size.height.clamp(self.min.height, self.max.height),
+        )
+    }
+
+    /// Returns `true` if only one size satisfies these constraints.
+    pub fn is_tight(&self) -> bool {
+        self.min == self.max
+    }
+}
+
+impl Default for Constraints {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+/// Gives a widget access to its children, and to the fonts its text
+/// is measured with, during layout.
+///
+/// Widgets are responsible for sizing and positioning their children,
+/// child positions are relative to the top left of the parent.
+pub struct LayoutContext<'a> {
+    children: &'a mut [WidgetNode],
+    fonts: &'a Fonts,
+}
+
+impl<'a> LayoutContext<'a> {
+    pub(crate) fn new(children: &'a mut [WidgetNode], fonts: &'a Fonts) -> Self {
+        Self { children, fonts }
+    }
+
+    /// The fonts of the window, for widgets to shape and measure
+    /// their text with.
+    pub fn fonts(&self) -> &'a Fonts {
+        self.fonts
+    }
+
+    /// The number of children the widget has.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Returns `true` if the widget has no children.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// The sizing of the child at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn child_sizing(&self, index: usize) -> BoxSizing {
+        self.children[index].sizing()
+    }
+
+    /// Lays out the child at `index` and returns its size.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn layout_child(&mut self, index: usize, constraints: Constraints) -> Size {
+        self.children[index].layout(constraints, self.fonts)
+    }
+
+    /// Sets the position of the child at `index`, relative to its parent.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn position_child(&mut self, index: usize, position: Position) {
+        self.children[index].set_position(position);
+    }
+}
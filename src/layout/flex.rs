@@ -0,0 +1,855 @@
+use crate::{BoxSizing, Constraints, LayoutContext, Rect, Size, Sizing};
+
+/// The direction that a [`Flex`] layout places its items in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Axis {
+    /// Items are placed from left to right, like a row.
+    #[default]
+    Horizontal,
+    /// Items are placed from top to bottom, like a column.
+    Vertical,
+}
+
+/// How items are distributed along the main axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MainAxisAlignment {
+    /// Place items at the start of the main axis.
+    #[default]
+    Start,
+    /// Place items in the center of the main axis.
+    Center,
+    /// Place items at the end of the main axis.
+    End,
+    /// Place the free space evenly between items, with no space
+    /// before the first or after the last item.
+    SpaceBetween,
+    /// Place the free space evenly around items, with half the
+    /// space before the first and after the last item.
+    SpaceAround,
+}
+
+/// How items are placed along the cross axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrossAxisAlignment {
+    /// Place items at the start of the cross axis.
+    #[default]
+    Start,
+    /// Place items in the center of the cross axis.
+    Center,
+    /// Place items at the end of the cross axis.
+    End,
+    /// Stretch items to fill the cross axis.
+    Stretch,
+}
+
+/// The space between the edges of a container and its content.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Padding {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Padding {
+    /// Creates a new [`Padding`].
+    pub const fn new(top: f32, right: f32, bottom: f32, left: f32) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    /// Padding with the same value on every side.
+    pub const fn all(value: f32) -> Self {
+        Self::new(value, value, value, value)
+    }
+
+    /// Padding with the same `vertical` value on the top and bottom
+    /// and `horizontal` value on the left and right.
+    pub const fn symmetric(vertical: f32, horizontal: f32) -> Self {
+        Self::new(vertical, horizontal, vertical, horizontal)
+    }
+
+    /// The total horizontal padding.
+    pub const fn horizontal(&self) -> f32 {
+        self.left + self.right
+    }
+
+    /// The total vertical padding.
+    pub const fn vertical(&self) -> f32 {
+        self.top + self.bottom
+    }
+}
+
+/// An item in a [`Flex`] layout.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct FlexItem {
+    /// The preferred size of the item, which is the starting size on
+    /// the main axis before the item grows or shrinks.
+    pub size: Size,
+    /// How much of the free space on the main axis this item takes,
+    /// relative to the other items. Items with a weight of `0.0` don't grow.
+    pub grow: f32,
+    /// How much this item shrinks, relative to the other items, when there
+    /// isn't enough space. Items with a weight of `0.0` overflow instead.
+    pub shrink: f32,
+    /// The smallest size the item can shrink to.
+    pub min: Size,
+    /// The largest size the item can grow or stretch to.
+    pub max: Size,
+    /// Whether the item fills the cross axis, regardless of the
+    /// cross axis alignment.
+    pub stretch: bool,
+    /// How the item is sized on the cross axis. Items with a
+    /// [`Sizing::Fixed`] cross size aren't stretched by
+    /// [`CrossAxisAlignment::Stretch`].
+    pub cross_sizing: Sizing,
+}
+
+impl FlexItem {
+    /// Creates a [`FlexItem`] that doesn't grow or shrink.
+    pub const fn new(size: Size) -> Self {
+        Self {
+            size,
+            grow: 0.0,
+            shrink: 0.0,
+            min: Size::ZERO,
+            max: Size::splat(f32::INFINITY),
+            stretch: false,
+            cross_sizing: Sizing::Fit,
+        }
+    }
+
+    /// Sets the flex grow weight.
+    pub const fn grow(mut self, grow: f32) -> Self {
+        self.grow = grow;
+        self
+    }
+
+    /// Sets the flex shrink weight.
+    pub const fn shrink(mut self, shrink: f32) -> Self {
+        self.shrink = shrink;
+        self
+    }
+
+    /// Sets the minimum size.
+    pub const fn min(mut self, min: Size) -> Self {
+        self.min = min;
+        self
+    }
+
+    /// Sets the maximum size.
+    pub const fn max(mut self, max: Size) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Stretches the item to fill the cross axis.
+    pub const fn stretch(mut self) -> Self {
+        self.stretch = true;
+        self
+    }
+
+    /// Sets how the item is sized on the cross axis.
+    pub const fn cross_sizing(mut self, sizing: Sizing) -> Self {
+        self.cross_sizing = sizing;
+        self
+    }
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        Self::new(Size::ZERO)
+    }
+}
+
+/// A flexbox style layout that places items in a row or column.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Flex {
+    pub axis: Axis,
+    pub padding: Padding,
+    /// The space between items.
+    pub gap: f32,
+    pub main_axis_alignment: MainAxisAlignment,
+    pub cross_axis_alignment: CrossAxisAlignment,
+}
+
+impl Flex {
+    /// Creates a horizontal [`Flex`] layout.
+    pub fn row() -> Self {
+        Self::default()
+    }
+
+    /// Creates a vertical [`Flex`] layout.
+    pub fn column() -> Self {
+        Self {
+            axis: Axis::Vertical,
+            ..Self::default()
+        }
+    }
+
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn gap(mut self, gap: f32) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    pub fn main_axis_alignment(mut self, alignment: MainAxisAlignment) -> Self {
+        self.main_axis_alignment = alignment;
+        self
+    }
+
+    pub fn cross_axis_alignment(mut self, alignment: CrossAxisAlignment) -> Self {
+        self.cross_axis_alignment = alignment;
+        self
+    }
+
+    /// Computes the rectangle of each item within a container of `size`.
+    ///
+    /// Rectangles are relative to the top left of the container, and
+    /// are returned in the same order as `items`. Items that can't shrink
+    /// enough to fit in the container overflow it.
+    pub fn arrange(&self, size: Size, items: &[FlexItem]) -> Vec<Rect> {
+        if items.is_empty() {
+            return Vec::new();
+        }
+
+        let (main_start, cross_start) = self.main_cross(self.padding.left, self.padding.top);
+        let (main_padding, cross_padding) =
+            self.main_cross(self.padding.horizontal(), self.padding.vertical());
+        let (container_main, container_cross) = self.main_cross(size.width, size.height);
+        let inner_main = container_main - main_padding;
+        let inner_cross = container_cross - cross_padding;
+
+        let gaps = self.gap * (items.len() - 1) as f32;
+        let mains = self.resolve_main(inner_main - gaps, items);
+        let free = inner_main - gaps - mains.iter().sum::<f32>();
+
+        let count = items.len() as f32;
+        let (leading, spacing) = match self.main_axis_alignment {
+            MainAxisAlignment::Start => (0.0, 0.0),
+            MainAxisAlignment::Center => (free / 2.0, 0.0),
+            MainAxisAlignment::End => (free, 0.0),
+            MainAxisAlignment::SpaceBetween if free > 0.0 && items.len() > 1 => {
+                (0.0, free / (count - 1.0))
+            }
+            MainAxisAlignment::SpaceBetween => (0.0, 0.0),
+            MainAxisAlignment::SpaceAround if free > 0.0 => (free / count / 2.0, free / count),
+            MainAxisAlignment::SpaceAround => (free / 2.0, 0.0),
+        };
+
+        let mut cursor = main_start + leading;
+        let mut rects = Vec::with_capacity(items.len());
+
+        for (item, main) in items.iter().zip(mains) {
+            let item_cross = self.main_cross(item.size.width, item.size.height).1;
+            let (min_cross, max_cross) = (self.cross(item.min), self.cross(item.max));
+
+            let fixed = matches!(item.cross_sizing, Sizing::Fixed(_));
+            let stretch = item.stretch
+                || (self.cross_axis_alignment == CrossAxisAlignment::Stretch && !fixed);
+            let cross = if stretch && inner_cross.is_finite() {
+                inner_cross.max(0.0).min(max_cross).max(min_cross)
+            } else {
+                item_cross
+            };
+
+            let cross_offset = match self.cross_axis_alignment {
+                CrossAxisAlignment::Start | CrossAxisAlignment::Stretch => 0.0,
+                CrossAxisAlignment::Center => (inner_cross - cross) / 2.0,
+                CrossAxisAlignment::End => inner_cross - cross,
+            };
+
+            let (x, y) = self.main_cross(cursor, cross_start + cross_offset);
+            let (width, height) = self.main_cross(main, cross);
+            rects.push(Rect::new(x, y, width, height));
+
+            cursor += main + self.gap + spacing;
+        }
+
+        rects
+    }
+
+    /// Resolves the size of each item on the main axis, growing items when
+    /// there's free space and shrinking them when there isn't enough.
+    ///
+    /// Items that reach their minimum or maximum size are frozen, and the
+    /// remaining space is shared between the other items.
+    fn resolve_main(&self, available: f32, items: &[FlexItem]) -> Vec<f32> {
+        let bases: Vec<f32> = items.iter().map(|item| self.main(item.size)).collect();
+        let mut mains = bases.clone();
+
+        let free = available - bases.iter().sum::<f32>();
+        if free == 0.0 {
+            return mains;
+        }
+
+        // Shrinking is weighted by the size of the item so that
+        // larger items shrink more
+        let weights: Vec<f32> = items
+            .iter()
+            .zip(&bases)
+            .map(|(item, basis)| {
+                if free > 0.0 {
+                    item.grow.max(0.0)
+                } else {
+                    item.shrink.max(0.0) * basis
+                }
+            })
+            .collect();
+        let mut frozen: Vec<bool> = weights.iter().map(|weight| *weight <= 0.0).collect();
+
+        loop {
+            let total: f32 = (0..items.len())
+                .filter(|&i| !frozen[i])
+                .map(|i| weights[i])
+                .sum();
+            if total <= 0.0 {
+                break;
+            }
+
+            let used: f32 = (0..items.len())
+                .map(|i| if frozen[i] { mains[i] } else { bases[i] })
+                .sum();
+            let remaining = available - used;
+
+            let mut clamped = false;
+            for (i, item) in items.iter().enumerate() {
+                if frozen[i] {
+                    continue;
+                }
+
+                let target = bases[i] + remaining * weights[i] / total;
+                let value = target.min(self.main(item.max)).max(self.main(item.min));
+                mains[i] = value;
+                if value != target {
+                    frozen[i] = true;
+                    clamped = true;
+                }
+            }
+
+            if !clamped {
+                break;
+            }
+        }
+
+        mains
+    }
+
+    /// Lays out the children of a widget with this flex layout and
+    /// returns the size of the container.
+    ///
+    /// Children are first measured with an unbounded main axis, then
+    /// arranged according to their [`BoxSizing`]:
+    /// - [`Sizing::Fixed`] children keep their size, and overflow if
+    ///   there isn't enough space.
+    /// - [`Sizing::Fit`] children shrink down to their minimum size when
+    ///   there isn't enough space.
+    /// - [`Sizing::Flex`] children share the free space by weight, on the
+    ///   cross axis they stretch to fill the container.
+    ///
+    /// The container fits its children, unless it has flexible children
+    /// and the main axis is bounded, in which case it fills the main axis.
+    pub fn layout(&self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        let (max_main, max_cross) = self.main_cross(constraints.max.width, constraints.max.height);
+        let (main_padding, cross_padding) =
+            self.main_cross(self.padding.horizontal(), self.padding.vertical());
+        let inner_cross = (max_cross - cross_padding).max(0.0);
+
+        let (width, height) = self.main_cross(f32::INFINITY, inner_cross);
+        let measure = Constraints::loose(Size::new(width, height));
+
+        let measured: Vec<Size> = (0..ctx.len())
+            .map(|i| ctx.layout_child(i, measure))
+            .collect();
+        let items: Vec<FlexItem> = measured
+            .iter()
+            .enumerate()
+            .map(|(i, size)| self.item(*size, ctx.child_sizing(i)))
+            .collect();
+
+        let gaps = self.gap * items.len().saturating_sub(1) as f32;
+        let content_main =
+            items.iter().map(|item| self.main(item.size)).sum::<f32>() + gaps + main_padding;
+        let content_cross = items
+            .iter()
+            .map(|item| self.cross(item.size))
+            .fold(0.0, f32::max)
+            + cross_padding;
+
+        let grows = items.iter().any(|item| item.grow > 0.0);
+        let main = if grows && max_main.is_finite() {
+            max_main
+        } else {
+            content_main
+        };
+        let (width, height) = self.main_cross(main, content_cross);
+        let size = constraints.constrain(Size::new(width, height));
+
+        let rects = self.arrange(size, &items);
+        for (i, (rect, measured)) in rects.iter().zip(measured).enumerate() {
+            if rect.size != measured {
+                ctx.layout_child(i, Constraints::tight(rect.size));
+            }
+            ctx.position_child(i, rect.position);
+        }
+
+        size
+    }
+
+    /// Creates a [`FlexItem`] from the measured size and sizing of a child.
+    fn item(&self, size: Size, sizing: BoxSizing) -> FlexItem {
+        let (main, cross) = match self.axis {
+            Axis::Horizontal => (sizing.width, sizing.height),
+            Axis::Vertical => (sizing.height, sizing.width),
+        };
+
+        let mut item = FlexItem::new(size)
+            .min(sizing.min_size())
+            .max(sizing.max_size());
+
+        match main.sizing {
+            Sizing::Fixed(_) => {}
+            Sizing::Fit => item.shrink = 1.0,
+            Sizing::Flex(weight) => {
+                // Flexible items start from their minimum size
+                // and only take up the free space
+                let (width, height) = self.main_cross(main.min, self.cross(size));
+                item.size = Size::new(width, height);
+                item.grow = weight.max(0.0);
+            }
+        }
+
+        item.cross_sizing = cross.sizing;
+        if let Sizing::Flex(_) = cross.sizing {
+            item.stretch = true;
+        }
+
+        item
+    }
+
+    /// The size along the main axis.
+    fn main(&self, size: Size) -> f32 {
+        self.main_cross(size.width, size.height).0
+    }
+
+    /// The size along the cross axis.
+    fn cross(&self, size: Size) -> f32 {
+        self.main_cross(size.width, size.height).1
+    }
+    /// Maps a horizontal and vertical pair to a main and cross pair, the
+    /// mapping is its own inverse.
+    fn main_cross(&self, horizontal: f32, vertical: f32) -> (f32, f32) {
+        match self.axis {
+            Axis::Horizontal => (horizontal, vertical),
+            Axis::Vertical => (vertical, horizontal),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::tests::fonts;
+    use crate::{DrawContext, Widget, WidgetNode};
+
+    fn items(sizes: &[(f32, f32)]) -> Vec<FlexItem> {
+        sizes
+            .iter()
+            .map(|&(width, height)| FlexItem::new(Size::new(width, height)))
+            .collect()
+    }
+
+    #[test]
+    fn row_start() {
+        let rects = Flex::row().arrange(
+            Size::new(200.0, 100.0),
+            &items(&[(50.0, 20.0), (30.0, 40.0)]),
+        );
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 0.0, 50.0, 20.0),
+                Rect::new(50.0, 0.0, 30.0, 40.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn column_start() {
+        let rects = Flex::column().arrange(
+            Size::new(200.0, 100.0),
+            &items(&[(50.0, 20.0), (30.0, 40.0)]),
+        );
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 0.0, 50.0, 20.0),
+                Rect::new(0.0, 20.0, 30.0, 40.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn padding_and_gap() {
+        let flex = Flex::row()
+            .padding(Padding::new(5.0, 0.0, 0.0, 10.0))
+            .gap(8.0);
+        let rects = flex.arrange(
+            Size::new(200.0, 100.0),
+            &items(&[(50.0, 20.0), (30.0, 40.0)]),
+        );
+        assert_eq!(
+            rects,
+            [
+                Rect::new(10.0, 5.0, 50.0, 20.0),
+                Rect::new(68.0, 5.0, 30.0, 40.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn main_axis_center() {
+        let flex = Flex::row().main_axis_alignment(MainAxisAlignment::Center);
+        let rects = flex.arrange(
+            Size::new(100.0, 100.0),
+            &items(&[(20.0, 10.0), (40.0, 10.0)]),
+        );
+        assert_eq!(
+            rects,
+            [
+                Rect::new(20.0, 0.0, 20.0, 10.0),
+                Rect::new(40.0, 0.0, 40.0, 10.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn main_axis_end() {
+        let flex = Flex::column()
+            .padding(Padding::all(10.0))
+            .main_axis_alignment(MainAxisAlignment::End);
+        let rects = flex.arrange(
+            Size::new(100.0, 100.0),
+            &items(&[(20.0, 10.0), (40.0, 20.0)]),
+        );
+        assert_eq!(
+            rects,
+            [
+                Rect::new(10.0, 60.0, 20.0, 10.0),
+                Rect::new(10.0, 70.0, 40.0, 20.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn main_axis_space_between() {
+        let flex = Flex::row().main_axis_alignment(MainAxisAlignment::SpaceBetween);
+        let rects = flex.arrange(
+            Size::new(100.0, 10.0),
+            &items(&[(10.0, 10.0), (10.0, 10.0), (20.0, 10.0)]),
+        );
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 0.0, 10.0, 10.0),
+                Rect::new(40.0, 0.0, 10.0, 10.0),
+                Rect::new(80.0, 0.0, 20.0, 10.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn main_axis_space_around() {
+        let flex = Flex::row().main_axis_alignment(MainAxisAlignment::SpaceAround);
+        let rects = flex.arrange(
+            Size::new(100.0, 10.0),
+            &items(&[(10.0, 10.0), (10.0, 10.0)]),
+        );
+        assert_eq!(
+            rects,
+            [
+                Rect::new(20.0, 0.0, 10.0, 10.0),
+                Rect::new(70.0, 0.0, 10.0, 10.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn space_between_single_item() {
+        let flex = Flex::row().main_axis_alignment(MainAxisAlignment::SpaceBetween);
+        let rects = flex.arrange(Size::new(100.0, 10.0), &items(&[(10.0, 10.0)]));
+        assert_eq!(rects, [Rect::new(0.0, 0.0, 10.0, 10.0)]);
+    }
+
+    #[test]
+    fn cross_axis_alignment() {
+        let sizes = items(&[(10.0, 20.0)]);
+        let size = Size::new(100.0, 100.0);
+        let flex = Flex::row().padding(Padding::all(10.0));
+
+        let center = flex.cross_axis_alignment(CrossAxisAlignment::Center);
+        assert_eq!(
+            center.arrange(size, &sizes),
+            [Rect::new(10.0, 40.0, 10.0, 20.0)]
+        );
+
+        let end = flex.cross_axis_alignment(CrossAxisAlignment::End);
+        assert_eq!(
+            end.arrange(size, &sizes),
+            [Rect::new(10.0, 70.0, 10.0, 20.0)]
+        );
+
+        let stretch = flex.cross_axis_alignment(CrossAxisAlignment::Stretch);
+        assert_eq!(
+            stretch.arrange(size, &sizes),
+            [Rect::new(10.0, 10.0, 10.0, 80.0)]
+        );
+    }
+
+    #[test]
+    fn flex_grow() {
+        let items = [
+            FlexItem::new(Size::new(20.0, 10.0)),
+            FlexItem::new(Size::new(0.0, 10.0)).grow(1.0),
+            FlexItem::new(Size::new(10.0, 10.0)).grow(3.0),
+        ];
+        let rects = Flex::row()
+            .gap(10.0)
+            .arrange(Size::new(130.0, 10.0), &items);
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 0.0, 20.0, 10.0),
+                Rect::new(30.0, 0.0, 20.0, 10.0),
+                Rect::new(60.0, 0.0, 70.0, 10.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn grow_ignores_alignment() {
+        let flex = Flex::row().main_axis_alignment(MainAxisAlignment::End);
+        let items = [FlexItem::new(Size::new(10.0, 10.0)).grow(1.0)];
+        let rects = flex.arrange(Size::new(100.0, 10.0), &items);
+        assert_eq!(rects, [Rect::new(0.0, 0.0, 100.0, 10.0)]);
+    }
+
+    struct Block(Size, BoxSizing);
+
+    impl Widget for Block {
+        fn sizing(&self) -> BoxSizing {
+            self.1
+        }
+
+        fn layout(&mut self, _: Constraints, _: &mut LayoutContext) -> Size {
+            self.0
+        }
+
+        fn draw(&self, _: &mut DrawContext) {}
+    }
+
+    struct Container(Flex, Vec<(Size, BoxSizing)>);
+
+    impl Widget for Container {
+        fn build(&self) -> Vec<Box<dyn Widget>> {
+            self.1
+                .iter()
+                .map(|&(size, sizing)| Box::new(Block(size, sizing)) as Box<dyn Widget>)
+                .collect()
+        }
+
+        fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+            self.0.layout(constraints, ctx)
+        }
+
+        fn draw(&self, _: &mut DrawContext) {}
+    }
+
+    fn layout(flex: Flex, children: Vec<(Size, BoxSizing)>, max: Size) -> (Size, Vec<Rect>) {
+        let mut node = WidgetNode::new(Container(flex, children));
+        let size = node.layout(Constraints::loose(max), &fonts());
+        let rects = node
+            .children()
+            .iter()
+            .map(|child| Rect::from_parts(child.position(), child.size()))
+            .collect();
+        (size, rects)
+    }
+
+    #[test]
+    fn layout_fill_children() {
+        let flex = Flex::column()
+            .padding(Padding::all(10.0))
+            .gap(10.0)
+            .cross_axis_alignment(CrossAxisAlignment::Stretch);
+        let children = vec![
+            (Size::new(40.0, 20.0), BoxSizing::fit()),
+            (Size::new(60.0, 20.0), BoxSizing::fit().height(Sizing::FILL)),
+        ];
+        let (size, rects) = layout(flex, children, Size::new(200.0, 200.0));
+
+        assert_eq!(size, Size::new(80.0, 200.0));
+        assert_eq!(
+            rects,
+            [
+                Rect::new(10.0, 10.0, 60.0, 20.0),
+                Rect::new(10.0, 40.0, 60.0, 150.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_fit_container() {
+        let children = vec![
+            (Size::new(40.0, 20.0), BoxSizing::fit()),
+            (Size::new(60.0, 10.0), BoxSizing::fixed(30.0, 30.0)),
+        ];
+        let (size, rects) = layout(Flex::row().gap(5.0), children, Size::new(200.0, 200.0));
+
+        assert_eq!(size, Size::new(75.0, 30.0));
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 0.0, 40.0, 20.0),
+                Rect::new(45.0, 0.0, 30.0, 30.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_weighted_fill_with_max() {
+        let children = vec![
+            (Size::ZERO, BoxSizing::fill().max_width(10.0)),
+            (Size::ZERO, BoxSizing::fill()),
+            (Size::ZERO, BoxSizing::fill().width(Sizing::Flex(3.0))),
+        ];
+        let (size, rects) = layout(Flex::row(), children, Size::new(100.0, 10.0));
+
+        assert_eq!(size, Size::new(100.0, 10.0));
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 0.0, 10.0, 10.0),
+                Rect::new(10.0, 0.0, 22.5, 10.0),
+                Rect::new(32.5, 0.0, 67.5, 10.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_shrink_fit_children() {
+        let children = vec![
+            (Size::new(60.0, 10.0), BoxSizing::fit()),
+            (Size::new(20.0, 10.0), BoxSizing::fixed(20.0, 10.0)),
+            (Size::new(60.0, 10.0), BoxSizing::fit()),
+        ];
+        let (size, rects) = layout(Flex::row(), children, Size::new(100.0, 100.0));
+
+        assert_eq!(size, Size::new(100.0, 10.0));
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 0.0, 40.0, 10.0),
+                Rect::new(40.0, 0.0, 20.0, 10.0),
+                Rect::new(60.0, 0.0, 40.0, 10.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_shrink_to_min_size() {
+        let children = vec![
+            (Size::new(60.0, 10.0), BoxSizing::fit().min_width(55.0)),
+            (Size::new(60.0, 10.0), BoxSizing::fit()),
+        ];
+        let (_, rects) = layout(Flex::row(), children, Size::new(100.0, 100.0));
+
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 0.0, 55.0, 10.0),
+                Rect::new(55.0, 0.0, 45.0, 10.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_overflow_fixed_children() {
+        let children = vec![
+            (Size::ZERO, BoxSizing::fixed(60.0, 10.0)),
+            (Size::ZERO, BoxSizing::fixed(60.0, 10.0)),
+        ];
+        let flex = Flex::row().main_axis_alignment(MainAxisAlignment::Center);
+        let (size, rects) = layout(flex, children, Size::new(100.0, 100.0));
+
+        assert_eq!(size, Size::new(100.0, 10.0));
+        assert_eq!(
+            rects,
+            [
+                Rect::new(-10.0, 0.0, 60.0, 10.0),
+                Rect::new(50.0, 0.0, 60.0, 10.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_overflow_min_size() {
+        let children = vec![
+            (Size::new(80.0, 10.0), BoxSizing::fit().min_width(70.0)),
+            (Size::new(80.0, 10.0), BoxSizing::fit().min_width(70.0)),
+        ];
+        let (_, rects) = layout(Flex::row(), children, Size::new(100.0, 100.0));
+
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 0.0, 70.0, 10.0),
+                Rect::new(70.0, 0.0, 70.0, 10.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_stretch_fill_cross_axis() {
+        let children = vec![
+            (Size::new(10.0, 40.0), BoxSizing::fit()),
+            (Size::new(10.0, 10.0), BoxSizing::fit().height(Sizing::FILL)),
+        ];
+        let flex = Flex::row().cross_axis_alignment(CrossAxisAlignment::Center);
+        let (_, rects) = layout(flex, children, Size::new(100.0, 100.0));
+
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 30.0, 10.0, 40.0),
+                Rect::new(10.0, 0.0, 10.0, 100.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_stretch_keeps_fixed_size() {
+        let children = vec![
+            (Size::new(10.0, 40.0), BoxSizing::fit()),
+            (Size::new(20.0, 20.0), BoxSizing::fixed(20.0, 20.0)),
+        ];
+        let flex = Flex::row().cross_axis_alignment(CrossAxisAlignment::Stretch);
+        let (_, rects) = layout(flex, children, Size::new(100.0, 100.0));
+
+        assert_eq!(
+            rects,
+            [
+                Rect::new(0.0, 0.0, 10.0, 40.0),
+                Rect::new(10.0, 0.0, 20.0, 20.0)
+            ]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{Constraints, Size};
+
+/// How a widget wants to be sized along one axis.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub enum Sizing {
+    /// A fixed size in logical pixels.
+    Fixed(f32),
+    /// Fit the size of the content.
+    #[default]
+    Fit,
+    /// Fill the available space, sharing it with other flexible
+    /// siblings in proportion to the weight.
+    Flex(f32),
+}
+
+impl Sizing {
+    /// Fill the available space, with a flex weight of `1.0`.
+    pub const FILL: Self = Self::Flex(1.0);
+
+    /// The flex weight, which is `0.0` for non-flexible sizes.
+    pub fn flex(&self) -> f32 {
+        match self {
+            Self::Flex(weight) => weight.max(0.0),
+            _ => 0.0,
+        }
+    }
+}
+
+/// The [`Sizing`] of one axis along with its minimum and maximum bounds.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct AxisSizing {
+    pub sizing: Sizing,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl AxisSizing {
+    /// Creates an unbounded [`AxisSizing`].
+    pub const fn new(sizing: Sizing) -> Self {
+        Self {
+            sizing,
+            min: 0.0,
+            max: f32::INFINITY,
+        }
+    }
+
+    /// Clamps `value` to the minimum and maximum bounds.
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.max(self.min).min(self.max.max(self.min))
+    }
+
+    /// Narrows a parent's `min` and `max` constraints for this axis,
+    /// the parent's constraints take priority over the bounds.
+    fn constrain(&self, min: f32, max: f32) -> (f32, f32) {
+        let lower = self.min.clamp(min, max);
+        let upper = self.max.clamp(lower, max);
+
+        match self.sizing {
+            Sizing::Fixed(size) => {
+                let size = size.clamp(lower, upper);
+                (size, size)
+            }
+            Sizing::Fit => (lower, upper),
+            Sizing::Flex(_) if upper.is_finite() => (upper, upper),
+            Sizing::Flex(_) => (lower, upper),
+        }
+    }
+}
+
+impl Default for AxisSizing {
+    fn default() -> Self {
+        Self::new(Sizing::Fit)
+    }
+}
+
+impl From<Sizing> for AxisSizing {
+    fn from(sizing: Sizing) -> Self {
+        Self::new(sizing)
+    }
+}
+
+/// How a widget wants to be sized on both axes.
+///
+/// Sizing is resolved in two passes, parents first measure their children
+/// and then arrange them, giving flexible children the remaining space and
+/// shrinking children that fit their content when there isn't enough space.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct BoxSizing {
+    pub width: AxisSizing,
+    pub height: AxisSizing,
+}
+
+impl BoxSizing {
+    /// Creates a new [`BoxSizing`].
+    pub fn new(width: Sizing, height: Sizing) -> Self {
+        Self {
+            width: width.into(),
+            height: height.into(),
+        }
+    }
+
+    /// A fixed width and height.
+    pub fn fixed(width: f32, height: f32) -> Self {
+        Self::new(Sizing::Fixed(width), Sizing::Fixed(height))
+    }
+
+    /// Fit the content on both axes.
+    pub fn fit() -> Self {
+        Self::default()
+    }
+
+    /// Fill the available space on both axes.
+    pub fn fill() -> Self {
+        Self::new(Sizing::FILL, Sizing::FILL)
+    }
+
+    pub fn width(mut self, sizing: Sizing) -> Self {
+        self.width.sizing = sizing;
+        self
+    }
+
+    pub fn height(mut self, sizing: Sizing) -> Self {
+        self.height.sizing = sizing;
+        self
+    }
+
+    pub fn min_width(mut self, min: f32) -> Self {
+        self.width.min = min;
+        self
+    }
+
+    pub fn max_width(mut self, max: f32) -> Self {
+        self.width.max = max;
+        self
+    }
+
+    pub fn min_height(mut self, min: f32) -> Self {
+        self.height.min = min;
+        self
+    }
+
+    pub fn max_height(mut self, max: f32) -> Self {
+        self.height.max = max;
+        self
+    }
+
+    /// The minimum size allowed by the bounds.
+    pub fn min_size(&self) -> Size {
+        Size::new(self.width.min, self.height.min)
+    }
+
+    /// The maximum size allowed by the bounds.
+    pub fn max_size(&self) -> Size {
+        Size::new(self.width.max, self.height.max)
+    }
+
+    /// Narrows the constraints given by a parent to the ones this
+    /// sizing allows.
+    ///
+    /// Fixed sizes become tight, and flexible sizes fill the maximum
+    /// size when it's bounded.
+    pub fn constrain(&self, constraints: Constraints) -> Constraints {
+        let (min_width, max_width) = self
+            .width
+            .constrain(constraints.min.width, constraints.max.width);
+        let (min_height, max_height) = self
+            .height
+            .constrain(constraints.min.height, constraints.max.height);
+
+        Constraints::new(
+            Size::new(min_width, min_height),
+            Size::new(max_width, max_height),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_sizing_is_tight() {
+        let constraints = BoxSizing::fixed(50.0, 20.0).constrain(Constraints::unbounded());
+        assert_eq!(constraints, Constraints::tight(Size::new(50.0, 20.0)));
+    }
+
+    #[test]
+    fn parent_constraints_take_priority() {
+        let parent = Constraints::loose(Size::new(40.0, 100.0));
+        let constraints = BoxSizing::fixed(50.0, 20.0)
+            .min_height(30.0)
+            .constrain(parent);
+        assert_eq!(constraints, Constraints::tight(Size::new(40.0, 30.0)));
+    }
+
+    #[test]
+    fn fit_sizing_uses_bounds() {
+        let sizing = BoxSizing::fit().min_width(10.0).max_width(80.0);
+        let constraints = sizing.constrain(Constraints::loose(Size::new(100.0, 100.0)));
+        assert_eq!(
+            constraints,
+            Constraints::new(Size::new(10.0, 0.0), Size::new(80.0, 100.0))
+        );
+    }
+
+    #[test]
+    fn flex_sizing_fills_bounded_space() {
+        let sizing = BoxSizing::fill().max_height(60.0);
+        let constraints = sizing.constrain(Constraints::loose(Size::new(100.0, 100.0)));
+        assert_eq!(constraints, Constraints::tight(Size::new(100.0, 60.0)));
+    }
+
+    #[test]
+    fn flex_sizing_in_unbounded_space() {
+        let constraints = BoxSizing::fill().constrain(Constraints::unbounded());
+        assert_eq!(constraints, Constraints::unbounded());
+    }
+}
@@ -1,68 +1,71 @@
-use bytemuck::{Pod, Zeroable};
-use wgpu::{
-    include_wgsl, wgt::DeviceDescriptor, Backends, Device, Features, Instance, InstanceDescriptor, PipelineLayoutDescriptor, Queue, RenderPass, RequestAdapterOptions, ShaderModuleDescriptor, ShaderStages, Surface, SurfaceConfiguration, TextureFormat, TextureUsages, VertexFormat
+mod app;
+mod atlas;
+mod brush;
+mod clip;
+mod color;
+mod draw;
+mod error;
+mod geometry;
+mod globals;
+mod glyph;
+mod layout;
+mod mesh;
+mod paragraph;
+mod shape;
+mod surface;
+mod text;
+mod texture;
+mod transform;
+mod widget;
+
+pub use app::{App, AppHandle, WindowConfig};
+pub use brush::{Brush, ConicGradient, GradientStop, LinearGradient, RadialGradient};
+pub use color::{Color, ParseColorError};
+pub use draw::DrawContext;
+pub use error::Error;
+pub use geometry::{Position, Rect, Size};
+pub use mesh::{Indices, Mesh};
+pub use paragraph::{Caret, Line, Paragraph, ParagraphLayout, Span, Text, TextAlign};
+pub use layout::{
+    Axis, AxisSizing, BoxSizing, Constraints, CrossAxisAlignment, Flex, FlexItem, LayoutContext,
+    MainAxisAlignment, Padding, Sizing,
 };
-use winit::{
-    application::ApplicationHandler,
-    event::WindowEvent,
-    event_loop::{self, EventLoop},
-    window::{Window, WindowAttributes},
+pub use shape::{Border, BoxShadow, CornerRadii, Outline, RectInstance, RoundedRect};
+pub use text::{
+    Font, FontError, FontWeight, Fonts, LineMetrics, ShapedGlyph, ShapedText, TextStyle,
 };
+pub use texture::{Image, ImageData, ImageError, ImageFit, ImageSampling};
+pub use transform::Transform;
+pub use widget::{Widget, WidgetNode};
 
-pub trait Widget {
-    fn build();
-    fn draw();
-    fn layout();
-}
-
-#[derive(Debug, Default)]
-pub struct App {
-    window: Option<Window>,
-}
-
-impl App {
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    pub fn run(mut self) {
-        let event_loop = EventLoop::new().unwrap();
-
-        event_loop.run_app(&mut self).unwrap();
-    }
-}
-
-impl ApplicationHandler for App {
-    fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        let attrs = WindowAttributes::default();
-        let window = event_loop
-            .create_window(attrs)
-            .expect("Failed to create window");
+use std::num::NonZeroU64;
+use std::sync::{Arc, OnceLock, mpsc};
 
-        self.window = Some(window);
-    }
-
-    fn window_event(
-        &mut self,
-        event_loop: &winit::event_loop::ActiveEventLoop,
-        window_id: winit::window::WindowId,
-        event: winit::event::WindowEvent,
-    ) {
-        let window = self.window.as_ref().expect("No window present");
-
-        match event {
-            WindowEvent::CloseRequested => {
-                event_loop.exit();
-            }
-            WindowEvent::RedrawRequested => {
-                window.pre_present_notify();
-            }
-            _ => {}
-        }
-    }
-}
+use bytemuck::{Pod, Zeroable};
+use clip::ClipShape;
+use draw::{BatchKind, DrawList};
+use globals::Globals;
+use glyph::GlyphAtlas;
+use image::RgbaImage;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BufferAsyncError, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, BlendState, BufferBinding, BufferBindingType, ShaderStages, CompositeAlphaMode, Buffer, BufferAddress, BufferDescriptor, BufferUsages,
+    ColorTargetState, ColorWrites, CommandEncoderDescriptor, Device, Extent3d, FragmentState,
+    Instance, InstanceDescriptor, LoadOp, MapMode, Operations, PipelineCompilationOptions, PipelineLayoutDescriptor, ShaderModuleDescriptor, ShaderSource,
+    PollType, PrimitiveState, PrimitiveTopology, Queue, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, RequestAdapterOptions,
+    StoreOp, Surface, SurfaceCapabilities, SurfaceConfiguration, TexelCopyBufferInfo, TexelCopyBufferLayout,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, VertexAttribute,
+    VertexBufferLayout, VertexFormat, VertexState, VertexStepMode,
+};
+use surface::{acquire_frame, surface_size, WindowSurface};
+use texture::TextureCache;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use winit::window::Window;
 
 /// Represents a single vertex with a 2D position, color and uv coordinates.
+///
+/// Positions are in logical pixels, with the origin at the top left.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Pod, Default, Zeroable)]
 pub struct Vertex {
@@ -72,123 +75,965 @@ pub struct Vertex {
 }
 
 impl Vertex {
-    /// Creates a new [`Vertex`]
-    pub fn new(x: f32, y: f32, color: [f32; 4]) -> Self {
+    const ATTRIBUTES: [VertexAttribute; 3] = [
+        VertexAttribute {
+            format: VertexFormat::Float32x2,
+            offset: std::mem::offset_of!(Vertex, position) as BufferAddress,
+            shader_location: 0,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(Vertex, color) as BufferAddress,
+            shader_location: 1,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x2,
+            offset: std::mem::offset_of!(Vertex, uv) as BufferAddress,
+            shader_location: 2,
+        },
+    ];
+
+    /// Describes how a buffer of [`Vertex`]'s is laid out in memory.
+    pub fn layout() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: size_of::<Vertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+
+    /// Creates a new [`Vertex`], converting the color to linear space.
+    pub fn new(x: f32, y: f32, color: Color) -> Self {
         Self {
             position: [x, y],
-            color,
+            color: color.to_linear(),
             uv: [1.0, 1.0],
         }
     }
 
     /// Creates a `Vec` of 6 `Vertices` in a quad layout.
     ///
+    /// This allocates and duplicates two of the corners, use
+    /// [`Mesh::push_quad`] when drawing many quads.
+    ///
     /// # Example
     /// ```
-    /// use helium_core::{Size,Position,Color};
-    /// use helium_renderer::Vertex;
-    ///
-    /// let size = Size::new(50.0,75.0);
-    /// let position = Position::default();
-    /// let color = Color::default();
+    /// use ruby::{Color, Vertex};
     ///
-    /// let vertices = Vertex::quad(size,position,color);
+    /// let vertices = Vertex::quad(50.0, 75.0, 10.0, 20.0, Color::BLACK);
     ///
-    /// assert_eq!(vertices[0].position[0],position.x);
-    /// assert_eq!(vertices[5].position[0],position.x + size.width);
+    /// assert_eq!(vertices[0].position[0], 10.0);
+    /// assert_eq!(vertices[5].position[0], 10.0 + 50.0);
     /// ```
-    pub fn quad(width: f32, height: f32, x: f32, y: f32) -> Vec<Self>{
+    pub fn quad(width: f32, height: f32, x: f32, y: f32, color: Color) -> Vec<Self>{
 
-        let vertex1 = Vertex::new(x, y, [0.0, 0.0,0.0,1.0]); //Top left
-        let vertex2 = Vertex::new(x + width, y, [0.0, 0.0,0.0,1.0]); // Top right
-        let vertex3 = Vertex::new(x, y + height,[0.0, 0.0,0.0,1.0]); //Bottom left
-        let vertex4 = Vertex::new(x + width, y, [0.0, 0.0,0.0,1.0]); //Top right
-        let vertex5 = Vertex::new(x, y + height, [0.0, 0.0,0.0,1.0]); // Bottom left
-        let vertex6 = Vertex::new(x + width, y + height, [0.0, 0.0,0.0,1.0]); //Bottom right
+        let vertex1 = Vertex::new(x, y, color); //Top left
+        let vertex2 = Vertex::new(x + width, y, color); // Top right
+        let vertex3 = Vertex::new(x, y + height, color); //Bottom left
+        let vertex4 = Vertex::new(x + width, y, color); //Top right
+        let vertex5 = Vertex::new(x, y + height, color); // Bottom left
+        let vertex6 = Vertex::new(x + width, y + height, color); //Bottom right
 
-        return vec![vertex1, vertex2, vertex3, vertex4, vertex5, vertex6];
+        vec![vertex1, vertex2, vertex3, vertex4, vertex5, vertex6]
     }
 }
 
-pub struct State<'a>{
-	surface: Surface<'a>,
+/// The texture that a [`State`] draws into.
+#[derive(Debug)]
+enum RenderTarget {
+	/// A window surface, presented to the screen after every frame.
+	Surface {
+		surface: Surface<'static>,
+		config: SurfaceConfiguration,
+		capabilities: SurfaceCapabilities,
+	},
+	/// An offscreen texture, used when rendering without a window.
+	Texture(wgpu::Texture),
+}
+
+#[derive(Debug)]
+pub struct State{
 	device: Device,
 	queue: Queue,
-	config: SurfaceConfiguration,
+	target: RenderTarget,
 	size: winit::dpi::PhysicalSize<u32>,
-	window: &'a Window
+	/// The ratio of physical pixels to logical pixels.
+	scale_factor: f64,
+	window: Option<Arc<Window>>,
+	pipeline: RenderPipeline,
+	rect_pipeline: RenderPipeline,
+	image_pipeline: RenderPipeline,
+	vertex_buffer: Buffer,
+	index_buffer: Buffer,
+	/// The unit quad that every rectangle instance is drawn with.
+	unit_quad: Buffer,
+	rect_buffer: Buffer,
+	/// The projection and scale factor, shared by every pipeline.
+	globals_buffer: Buffer,
+	globals_bind_group: BindGroup,
+	/// The shapes that batches are clipped to, one per dynamic offset.
+	clip_buffer: Buffer,
+	clip_layout: BindGroupLayout,
+	clip_bind_group: BindGroup,
+	/// The gradient stops of the brushes used by rectangles.
+	gradient_buffer: Buffer,
+	gradient_layout: BindGroupLayout,
+	gradient_bind_group: BindGroup,
+	/// The rasterized glyphs that text is drawn with.
+	glyph_atlas: GlyphAtlas,
+	/// The textures of the images drawn recently.
+	textures: TextureCache,
+	/// The fonts that widgets measure their text with during layout,
+	/// which are loaded the first time they're used.
+	fonts: OnceLock<Fonts>,
+	/// The geometry queued for the next frame.
+	draw_list: DrawList,
+	/// The color the frame is cleared to before drawing.
+	clear_color: wgpu::Color,
 }
 
-impl<'a> State<'a> {
-	async fn new(window: &'a Window) -> Self{
-		let size = window.inner_size();
+impl State {
+	/// The texture format of the offscreen target, chosen so that frames
+	/// can be copied straight into an [`RgbaImage`].
+	const HEADLESS_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+	/// The size in bytes of each vertex buffer before it needs to grow.
+	const INITIAL_BUFFER_SIZE: u64 = 64 * 1024;
+
+	pub async fn new(window: Arc<Window>) -> Result<Self, Error>{
+		// The surface is configured at 1x1 until the window is first
+		// resized to a size with area
+		let size = surface_size(window.inner_size())
+			.unwrap_or(winit::dpi::PhysicalSize::new(1, 1));
 
 		let instance = Instance::new(&InstanceDescriptor { 
 			backends: wgpu::Backends::PRIMARY, 
 			..Default::default()
 		});
 
-		let surface = instance.create_surface(window).unwrap();
+		let surface = instance.create_surface(Arc::clone(&window))?;
 
 		let adapter = instance.request_adapter(&RequestAdapterOptions{
 			compatible_surface: Some(&surface),
 			..Default::default()
-		}).await.unwrap();
+		}).await?;
 
-		let (device,queue) = adapter.request_device(&Default::default())
-			.await
-			.unwrap();
+		let (device,queue) = adapter.request_device(&Default::default()).await?;
 
 		let caps = surface.get_capabilities(&adapter);
 
+		// Without an sRGB format, the shaders encode colors themselves
 		let format = caps.formats
 			.iter()
 			.find(|f|f.is_srgb())
+			.or(caps.formats.first())
 			.copied()
-			.unwrap_or(caps.formats[0]);
+			.ok_or(Error::IncompatibleSurface)?;
+		if !format.is_srgb() {
+			log::info!("No sRGB surface format, encoding colors for {format:?} in shaders");
+		}
+		let present_mode = caps.present_modes.first().copied().ok_or(Error::IncompatibleSurface)?;
+		let alpha_mode = caps.alpha_modes.first().copied().ok_or(Error::IncompatibleSurface)?;
 
 		let config = wgpu::SurfaceConfiguration{
 			usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
 			width: size.width,
 			height: size.height,
-			present_mode: caps.present_modes[0],
-			alpha_mode: caps.alpha_modes[0],
+			present_mode,
+			alpha_mode,
 			view_formats: vec![],
 			desired_maximum_frame_latency: 2,
 			format,
 		};
 
-		Self{
-			surface,
+		surface.configure(&device, &config);
+
+		let scale_factor = window.scale_factor();
+		let target = RenderTarget::Surface { surface, config, capabilities: caps };
+		let mut state = Self::with_target(device, queue, format, target, size, scale_factor);
+		state.window = Some(window);
+		Ok(state)
+	}
+
+	/// Creates a [`State`] that renders into an offscreen texture instead
+	/// of a window, for environments without a display.
+	///
+	/// Backends can be selected with the `WGPU_BACKEND` environment variable,
+	/// and a software adapter is used if no hardware adapter is available.
+	/// Use [`State::capture`] to read back the rendered frame.
+	pub async fn headless(width: u32, height: u32) -> Result<Self, Error> {
+		Self::headless_with_format(width, height, Self::HEADLESS_FORMAT).await
+	}
+
+	/// Creates a headless [`State`] whose target has the texture `format`,
+	/// which must have the same layout as [`State::HEADLESS_FORMAT`] to
+	/// be captured.
+	async fn headless_with_format(width: u32, height: u32, format: TextureFormat) -> Result<Self, Error> {
+		let size = winit::dpi::PhysicalSize::new(width.max(1), height.max(1));
+
+		let instance = Instance::new(&InstanceDescriptor::from_env_or_default());
+
+		let adapter = match instance.request_adapter(&RequestAdapterOptions::default()).await {
+			Ok(adapter) => adapter,
+			Err(_) => instance.request_adapter(&RequestAdapterOptions{
+				force_fallback_adapter: true,
+				..Default::default()
+			}).await?,
+		};
+
+		let (device,queue) = adapter.request_device(&Default::default()).await?;
+
+		let texture = Self::create_offscreen_texture(&device, size, format);
+		let target = RenderTarget::Texture(texture);
+		Ok(Self::with_target(device, queue, format, target, size, 1.0))
+	}
+
+	/// Creates the pipelines, buffers and atlases shared by window and
+	/// headless states, for a `target` whose texture format is `format`.
+	fn with_target(
+		device: Device,
+		queue: Queue,
+		format: TextureFormat,
+		target: RenderTarget,
+		size: winit::dpi::PhysicalSize<u32>,
+		scale_factor: f64,
+	) -> Self {
+		let globals_layout = Self::create_globals_layout(&device);
+		let (globals_buffer, globals_bind_group) =
+			Self::create_globals(&device, &globals_layout, Globals::new(size, scale_factor));
+		let clip_layout = Self::create_clip_layout(&device);
+		let gradient_layout = Self::create_gradient_layout(&device);
+		let glyph_atlas = GlyphAtlas::new(&device, &queue);
+		let textures = TextureCache::new(&device);
+		let (pipeline, rect_pipeline, image_pipeline) = Self::create_pipelines(
+			&device,
+			format,
+			&[&globals_layout, &clip_layout],
+			&gradient_layout,
+			glyph_atlas.layout(),
+			textures.layout(),
+		);
+		let vertex_buffer = Self::create_vertex_buffer(&device, "Vertex buffer", Self::INITIAL_BUFFER_SIZE);
+		let index_buffer = Self::create_buffer(&device, "Index buffer", Self::INITIAL_BUFFER_SIZE, BufferUsages::INDEX);
+		let unit_quad = Self::create_unit_quad(&device);
+		let rect_buffer = Self::create_vertex_buffer(&device, "Rect buffer", Self::INITIAL_BUFFER_SIZE);
+		let gradient_buffer = Self::create_buffer(&device, "Gradient buffer", Self::INITIAL_BUFFER_SIZE, BufferUsages::STORAGE);
+		let gradient_bind_group = Self::create_gradient_bind_group(&device, &gradient_layout, &gradient_buffer);
+		let clip_buffer = Self::create_buffer(&device, "Clip buffer", Self::INITIAL_BUFFER_SIZE, BufferUsages::UNIFORM);
+		let clip_bind_group = Self::create_clip_bind_group(&device, &clip_layout, &clip_buffer);
+
+		Self {
 			device,
 			queue,
-			config,
+			target,
 			size,
-			window
+			scale_factor,
+			window: None,
+			pipeline,
+			rect_pipeline,
+			image_pipeline,
+			vertex_buffer,
+			index_buffer,
+			unit_quad,
+			rect_buffer,
+			globals_buffer,
+			globals_bind_group,
+			clip_buffer,
+			clip_layout,
+			clip_bind_group,
+			gradient_buffer,
+			gradient_layout,
+			gradient_bind_group,
+			glyph_atlas,
+			textures,
+			fonts: OnceLock::new(),
+			draw_list: DrawList::default(),
+			clear_color: wgpu::Color::WHITE,
+		}
+	}
+
+	fn create_offscreen_texture(
+		device: &Device,
+		size: winit::dpi::PhysicalSize<u32>,
+		format: TextureFormat,
+	) -> wgpu::Texture {
+		device.create_texture(&TextureDescriptor {
+			label: Some("Headless target"),
+			size: Extent3d {
+				width: size.width,
+				height: size.height,
+				depth_or_array_layers: 1,
+			},
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: TextureDimension::D2,
+			format,
+			usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+			view_formats: &[],
+		})
+	}
+
+	fn create_pipeline(
+		device: &Device,
+		format: TextureFormat,
+		label: &str,
+		shader: ShaderModuleDescriptor,
+		vertex_layouts: &[VertexBufferLayout],
+		bind_group_layouts: &[&BindGroupLayout],
+	) -> RenderPipeline {
+		let shader = device.create_shader_module(shader);
+		// Colors are blended in linear space and encoded to sRGB by the
+		// target, targets that store colors as is need them encoded
+		let encode_srgb = if format.is_srgb() { 0.0 } else { 1.0 };
+
+		let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+			label: Some(&format!("{label} layout")),
+			bind_group_layouts,
+			push_constant_ranges: &[],
+		});
+
+		device.create_render_pipeline(&RenderPipelineDescriptor {
+			label: Some(label),
+			layout: Some(&layout),
+			vertex: VertexState {
+				module: &shader,
+				entry_point: Some("vs_main"),
+				compilation_options: Default::default(),
+				buffers: vertex_layouts,
+			},
+			fragment: Some(FragmentState {
+				module: &shader,
+				entry_point: Some("fs_main"),
+				compilation_options: PipelineCompilationOptions {
+					constants: &[("ENCODE_SRGB", encode_srgb)],
+					..Default::default()
+				},
+				targets: &[Some(ColorTargetState {
+					format,
+					blend: Some(BlendState::ALPHA_BLENDING),
+					write_mask: ColorWrites::ALL,
+				})],
+			}),
+			primitive: PrimitiveState {
+				topology: PrimitiveTopology::TriangleList,
+				..Default::default()
+			},
+			depth_stencil: None,
+			multisample: Default::default(),
+			multiview: None,
+			cache: None,
+		})
+	}
+
+	/// Creates a shader module from `source`, with the globals, clip
+	/// and helpers in `common.wgsl` prepended.
+	fn shader(label: &'static str, source: &str) -> ShaderModuleDescriptor<'static> {
+		let common = include_str!("shaders/common.wgsl");
+		ShaderModuleDescriptor {
+			label: Some(label),
+			source: ShaderSource::Wgsl(format!("{common}\n{source}").into()),
+		}
+	}
+
+	/// Creates the pipelines for plain vertices, rounded rectangles
+	/// and images.
+	///
+	/// `shared` are the globals and clip layouts used by every pipeline,
+	/// plain vertices also sample the glyph atlas, rectangles read
+	/// gradient stops and images sample their texture.
+	fn create_pipelines(
+		device: &Device,
+		format: TextureFormat,
+		shared: &[&BindGroupLayout; 2],
+		gradient_layout: &BindGroupLayout,
+		atlas_layout: &BindGroupLayout,
+		image_layout: &BindGroupLayout,
+	) -> (RenderPipeline, RenderPipeline, RenderPipeline) {
+		let [globals_layout, clip_layout] = *shared;
+		let pipeline = Self::create_pipeline(
+			device,
+			format,
+			"Render pipeline",
+			Self::shader("shader.wgsl", include_str!("shaders/shader.wgsl")),
+			&[Vertex::layout()],
+			&[globals_layout, clip_layout, atlas_layout],
+		);
+		let rect_pipeline = Self::create_pipeline(
+			device,
+			format,
+			"Rect pipeline",
+			Self::shader("rect.wgsl", include_str!("shaders/rect.wgsl")),
+			&[RectInstance::unit_quad_layout(), RectInstance::layout()],
+			&[globals_layout, clip_layout, gradient_layout],
+		);
+
+		let image_pipeline = Self::create_pipeline(
+			device,
+			format,
+			"Image pipeline",
+			Self::shader("image.wgsl", include_str!("shaders/image.wgsl")),
+			&[Vertex::layout()],
+			&[globals_layout, clip_layout, image_layout],
+		);
+
+		(pipeline, rect_pipeline, image_pipeline)
+	}
+
+	/// Creates the layout of the bind group holding the [`Globals`].
+	fn create_globals_layout(device: &Device) -> BindGroupLayout {
+		device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+			label: Some("Globals layout"),
+			entries: &[BindGroupLayoutEntry {
+				binding: 0,
+				visibility: ShaderStages::VERTEX_FRAGMENT,
+				ty: BindingType::Buffer {
+					ty: BufferBindingType::Uniform,
+					has_dynamic_offset: false,
+					min_binding_size: None,
+				},
+				count: None,
+			}],
+		})
+	}
+
+	fn create_globals(device: &Device, layout: &BindGroupLayout, globals: Globals) -> (Buffer, BindGroup) {
+		let buffer = device.create_buffer_init(&BufferInitDescriptor {
+			label: Some("Globals buffer"),
+			contents: bytemuck::bytes_of(&globals),
+			usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+		});
+
+		let bind_group = device.create_bind_group(&BindGroupDescriptor {
+			label: Some("Globals bind group"),
+			layout,
+			entries: &[BindGroupEntry {
+				binding: 0,
+				resource: buffer.as_entire_binding(),
+			}],
+		});
+
+		(buffer, bind_group)
+	}
+
+	/// Writes the projection and scale factor for the current size.
+	fn update_globals(&self) {
+		let globals = Globals::new(self.size, self.scale_factor);
+		self.queue.write_buffer(&self.globals_buffer, 0, bytemuck::bytes_of(&globals));
+	}
+
+	/// Creates the layout of the bind group holding a [`ClipShape`],
+	/// which is picked for each batch with a dynamic offset.
+	fn create_clip_layout(device: &Device) -> BindGroupLayout {
+		device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+			label: Some("Clip layout"),
+			entries: &[BindGroupLayoutEntry {
+				binding: 0,
+				visibility: ShaderStages::FRAGMENT,
+				ty: BindingType::Buffer {
+					ty: BufferBindingType::Uniform,
+					has_dynamic_offset: true,
+					min_binding_size: NonZeroU64::new(size_of::<ClipShape>() as u64),
+				},
+				count: None,
+			}],
+		})
+	}
+
+	fn create_clip_bind_group(device: &Device, layout: &BindGroupLayout, buffer: &Buffer) -> BindGroup {
+		device.create_bind_group(&BindGroupDescriptor {
+			label: Some("Clip bind group"),
+			layout,
+			entries: &[BindGroupEntry {
+				binding: 0,
+				resource: BindingResource::Buffer(BufferBinding {
+					buffer,
+					offset: 0,
+					size: NonZeroU64::new(size_of::<ClipShape>() as u64),
+				}),
+			}],
+		})
+	}
+
+	/// The distance between clip shapes in the clip buffer, which
+	/// dynamic offsets must be aligned to.
+	fn clip_stride(&self) -> u32 {
+		let alignment = self.device.limits().min_uniform_buffer_offset_alignment;
+		(size_of::<ClipShape>() as u32).next_multiple_of(alignment)
+	}
+
+	/// Creates the layout of the bind group holding the gradient stops,
+	/// which are read by the fragment shader.
+	fn create_gradient_layout(device: &Device) -> BindGroupLayout {
+		device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+			label: Some("Gradient layout"),
+			entries: &[BindGroupLayoutEntry {
+				binding: 0,
+				visibility: ShaderStages::FRAGMENT,
+				ty: BindingType::Buffer {
+					ty: BufferBindingType::Storage { read_only: true },
+					has_dynamic_offset: false,
+					min_binding_size: None,
+				},
+				count: None,
+			}],
+		})
+	}
+
+	fn create_gradient_bind_group(device: &Device, layout: &BindGroupLayout, buffer: &Buffer) -> BindGroup {
+		device.create_bind_group(&BindGroupDescriptor {
+			label: Some("Gradient bind group"),
+			layout,
+			entries: &[BindGroupEntry {
+				binding: 0,
+				resource: buffer.as_entire_binding(),
+			}],
+		})
+	}
+
+	fn create_unit_quad(device: &Device) -> Buffer {
+		device.create_buffer_init(&BufferInitDescriptor {
+			label: Some("Unit quad"),
+			contents: bytemuck::cast_slice(&RectInstance::UNIT_QUAD),
+			usage: BufferUsages::VERTEX,
+		})
+	}
+
+	fn create_vertex_buffer(device: &Device, label: &str, size: u64) -> Buffer {
+		Self::create_buffer(device, label, size, BufferUsages::VERTEX)
+	}
+
+	fn create_buffer(device: &Device, label: &str, size: u64, usage: BufferUsages) -> Buffer {
+		device.create_buffer(&BufferDescriptor {
+			label: Some(label),
+			size,
+			usage: usage | BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		})
+	}
+
+	/// Queues vertices to be drawn in the next frame.
+	///
+	/// Vertices are drawn as a triangle list, so the number of
+	/// vertices should be a multiple of three.
+	pub fn draw(&mut self, vertices: &[Vertex]) {
+		self.draw_list.push(vertices);
+	}
+
+	/// Queues a quad filled with a solid color or a gradient to be
+	/// drawn in the next frame.
+	pub fn draw_quad(&mut self, width: f32, height: f32, x: f32, y: f32, fill: impl Into<Brush>) {
+		self.draw_list.push_quad(Rect::new(x, y, width, height), fill);
+	}
+
+	/// Queues an indexed mesh to be drawn in the next frame.
+	pub fn draw_mesh(&mut self, mesh: &Mesh) {
+		self.draw_list.push_mesh(mesh);
+	}
+
+	/// Queues a rectangle with rounded corners to be drawn in the next frame.
+	pub fn draw_rect(&mut self, rect: &RoundedRect) {
+		self.draw_list.push_rect(rect);
+	}
+
+	/// Queues everything drawn into a [`DrawContext`].
+	pub fn draw_context(&mut self, ctx: &mut DrawContext) {
+		self.draw_list.append(&mut ctx.take_list());
+	}
+
+	/// The fonts that widgets measure their text with, which are set
+	/// with [`State::set_fonts`].
+	///
+	/// With the `bundled-font` feature, the bundled DejaVu Sans is
+	/// loaded the first time this is called if no fonts were set.
+	/// Otherwise it's `None` until fonts are set.
+	pub fn fonts(&self) -> Option<&Fonts> {
+		#[cfg(feature = "bundled-font")]
+		return Some(self.fonts.get_or_init(Fonts::default));
+		#[cfg(not(feature = "bundled-font"))]
+		return self.fonts.get();
+	}
+
+	pub fn set_fonts(&mut self, fonts: Fonts) {
+		self.fonts = OnceLock::from(fonts);
+	}
+
+	/// Returns the window being rendered to, or `None` for
+	/// headless states.
+	pub fn window(&self) -> Option<&Window>{
+		self.window.as_deref()
+	}
+
+	/// The size of the render target in physical pixels.
+	pub fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+		self.size
+	}
+
+	/// The texture format of the render target.
+	pub fn format(&self) -> TextureFormat {
+		match &self.target {
+			RenderTarget::Surface { config, .. } => config.format,
+			RenderTarget::Texture(texture) => texture.format(),
 		}
 	}
 
-	pub fn window(&self) -> &Window{
-		&self.window
+	/// The size of the render target in logical pixels.
+	pub fn logical_size(&self) -> winit::dpi::LogicalSize<f32> {
+		self.size.to_logical(self.scale_factor)
+	}
+
+	/// The ratio of physical pixels to logical pixels.
+	pub fn scale_factor(&self) -> f64 {
+		self.scale_factor
+	}
+
+	/// Updates the scale factor, usually when the window is moved
+	/// to a monitor with a different DPI.
+	pub fn set_scale_factor(&mut self, scale_factor: f64) {
+		self.scale_factor = scale_factor;
+		self.update_globals();
 	}
 
 	/// Resize the surface size when the window size changes.
 	/// 
 	/// Attempting to draw when the `Surface` and `Window` are 
 	/// different sizes will cause the program to crash.
-	fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        todo!()
-    }
+	///
+	/// Zero sized windows, which happens when a window is
+	/// minimized, are ignored and the previous size is kept.
+	pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+		let Some(new_size) = surface_size(new_size) else {
+			return;
+		};
 
-    fn input(&mut self, event: &WindowEvent) -> bool {
-        todo!()
-    }
+		self.size = new_size;
 
-    fn update(&mut self) {
-        todo!()
+		match &mut self.target {
+			RenderTarget::Surface { surface, config, .. } => {
+				config.width = new_size.width;
+				config.height = new_size.height;
+				surface.configure(&self.device, config);
+			}
+			RenderTarget::Texture(texture) => {
+				*texture = Self::create_offscreen_texture(&self.device, new_size, texture.format());
+			}
+		}
+
+		self.update_globals();
     }
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        todo!()
+	/// Makes the background of the frame transparent, so that content
+	/// behind a transparent window shows through.
+	///
+	/// Window surfaces switch to a compositing alpha mode if the
+	/// platform supports one.
+	pub fn set_transparent(&mut self, transparent: bool) {
+		self.clear_color = if transparent {
+			wgpu::Color::TRANSPARENT
+		} else {
+			wgpu::Color::WHITE
+		};
+
+		if let RenderTarget::Surface { surface, config, capabilities } = &mut self.target {
+			let alpha_modes = &capabilities.alpha_modes;
+			config.alpha_mode = alpha_modes
+				.iter()
+				.copied()
+				.find(|mode| {
+					transparent && matches!(
+						mode,
+						CompositeAlphaMode::PreMultiplied | CompositeAlphaMode::PostMultiplied
+					)
+				})
+				.or(alpha_modes.first().copied())
+				.unwrap_or(config.alpha_mode);
+			surface.configure(&self.device, config);
+		}
+	}
+
+	/// Draws all the vertices queued since the last frame in a
+	/// single render pass.
+	///
+	/// Lost or outdated surfaces are reconfigured automatically, and
+	/// the frame is skipped if the surface times out.
+    pub fn render(&mut self) -> Result<(), Error> {
+		let (frame, view) = match &self.target {
+			RenderTarget::Surface { surface, config, .. } => {
+				let mut surface = WindowSurface {
+					surface,
+					device: &self.device,
+					config,
+				};
+				let Some(frame) = acquire_frame(&mut surface)? else {
+					self.draw_list.clear();
+					return Ok(());
+				};
+				let view = frame.texture.create_view(&Default::default());
+				(Some(frame), view)
+			}
+			RenderTarget::Texture(texture) => (None, texture.create_view(&Default::default())),
+		};
+
+		let mut quads = self.glyph_atlas.prepare(&self.device, &self.queue, &mut self.draw_list);
+		quads.extend(self.textures.prepare(&self.device, &self.queue, &mut self.draw_list));
+		self.draw_list.assign_pages(quads);
+		self.upload_vertices();
+
+		let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
+			label: Some("Render encoder"),
+		});
+
+		let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+			label: Some("Render pass"),
+			color_attachments: &[Some(RenderPassColorAttachment {
+				view: &view,
+				resolve_target: None,
+				ops: Operations {
+					load: LoadOp::Clear(self.clear_color),
+					store: StoreOp::Store,
+				},
+			})],
+			..Default::default()
+		});
+
+		let clip_stride = self.clip_stride();
+		for batch in &self.draw_list.batches {
+			let full = Rect::new(0.0, 0.0, f32::INFINITY, f32::INFINITY);
+			let scissor = batch.scissor.unwrap_or(full);
+			let Some([x, y, width, height]) = clip::scissor_rect(scissor, self.scale_factor, self.size) else {
+				continue;
+			};
+			pass.set_scissor_rect(x, y, width, height);
+
+			// The first shape in the buffer doesn't clip anything
+			let shape = batch.clip_shape.map_or(0, |shape| shape + 1);
+			pass.set_bind_group(1, &self.clip_bind_group, &[shape * clip_stride]);
+
+			match batch.kind {
+				BatchKind::Mesh => {
+					let format = self.draw_list.mesh.indices().format();
+					pass.set_pipeline(&self.pipeline);
+					pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+					pass.set_index_buffer(self.index_buffer.slice(..), format);
+					pass.set_bind_group(0, &self.globals_bind_group, &[]);
+					pass.set_bind_group(2, self.glyph_atlas.bind_group(batch.page), &[]);
+					pass.draw_indexed(batch.range.clone(), 0, 0..1);
+				}
+				BatchKind::Rect => {
+					pass.set_pipeline(&self.rect_pipeline);
+					pass.set_vertex_buffer(0, self.unit_quad.slice(..));
+					pass.set_vertex_buffer(1, self.rect_buffer.slice(..));
+					pass.set_bind_group(0, &self.globals_bind_group, &[]);
+					pass.set_bind_group(2, &self.gradient_bind_group, &[]);
+					pass.draw(0..RectInstance::UNIT_QUAD.len() as u32, batch.range.clone());
+				}
+				BatchKind::Image(_) | BatchKind::AtlasImage(_) => {
+					let Some(bind_group) = self.textures.bind_group(batch) else {
+						continue;
+					};
+					let format = self.draw_list.mesh.indices().format();
+					pass.set_pipeline(&self.image_pipeline);
+					pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+					pass.set_index_buffer(self.index_buffer.slice(..), format);
+					pass.set_bind_group(0, &self.globals_bind_group, &[]);
+					pass.set_bind_group(2, bind_group, &[]);
+					pass.draw_indexed(batch.range.clone(), 0, 0..1);
+				}
+			}
+		}
+
+		drop(pass);
+
+		self.queue.submit(std::iter::once(encoder.finish()));
+		self.draw_list.clear();
+
+		if let Some(frame) = frame {
+			if let Some(window) = &self.window {
+				window.pre_present_notify();
+			}
+			frame.present();
+		}
+
+		Ok(())
     }
-}
\ No newline at end of file
+
+	/// Writes the queued vertices into the vertex buffers.
+	fn upload_vertices(&mut self) {
+		let (device, queue) = (&self.device, &self.queue);
+		let mesh = &self.draw_list.mesh;
+		Self::upload(device, queue, &mut self.vertex_buffer, "Vertex buffer", bytemuck::cast_slice(mesh.vertices()));
+		Self::upload(device, queue, &mut self.index_buffer, "Index buffer", mesh.indices().as_bytes());
+
+		let rects = bytemuck::cast_slice(&self.draw_list.rects);
+		Self::upload(device, queue, &mut self.rect_buffer, "Rect buffer", rects);
+
+		let stops = bytemuck::cast_slice(&self.draw_list.gradient_stops);
+		if Self::upload(device, queue, &mut self.gradient_buffer, "Gradient buffer", stops) {
+			self.gradient_bind_group =
+				Self::create_gradient_bind_group(device, &self.gradient_layout, &self.gradient_buffer);
+		}
+
+		let stride = self.clip_stride() as usize;
+		let shapes = std::iter::once(ClipShape::default()).chain(self.draw_list.clip_shapes.iter().copied());
+		let mut clips = vec![0; stride * (self.draw_list.clip_shapes.len() + 1)];
+		for (shape, bytes) in shapes.zip(clips.chunks_mut(stride)) {
+			bytes[..size_of::<ClipShape>()].copy_from_slice(bytemuck::bytes_of(&shape));
+		}
+		if Self::upload(device, queue, &mut self.clip_buffer, "Clip buffer", &clips) {
+			self.clip_bind_group = Self::create_clip_bind_group(device, &self.clip_layout, &self.clip_buffer);
+		}
+	}
+
+	/// Writes `bytes` into `buffer`, growing the buffer if it doesn't fit.
+	///
+	/// Returns `true` if the buffer was replaced, in which case bind
+	/// groups using it need to be recreated.
+	fn upload(device: &Device, queue: &Queue, buffer: &mut Buffer, label: &str, bytes: &[u8]) -> bool {
+		if bytes.is_empty() {
+			return false;
+		}
+
+		// Writes must be a multiple of 4 bytes, which an odd number
+		// of `u16` indices isn't
+		let needed = (bytes.len() as u64).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+		let grown = needed > buffer.size();
+		if grown {
+			let usage = buffer.usage() - BufferUsages::COPY_DST;
+			*buffer = Self::create_buffer(device, label, needed.next_power_of_two(), usage);
+		}
+
+		let aligned = bytes.len() - bytes.len() % wgpu::COPY_BUFFER_ALIGNMENT as usize;
+		let (body, tail) = bytes.split_at(aligned);
+		if !body.is_empty() {
+			queue.write_buffer(buffer, 0, body);
+		}
+		if !tail.is_empty() {
+			let mut padded = [0; wgpu::COPY_BUFFER_ALIGNMENT as usize];
+			padded[..tail.len()].copy_from_slice(tail);
+			queue.write_buffer(buffer, aligned as u64, &padded);
+		}
+
+		grown
+	}
+
+	/// Reads the last rendered frame back from the GPU.
+	///
+	/// Returns [`Error::CaptureWindow`] if this state renders to a window,
+	/// since surface textures can't be copied from.
+	pub fn capture(&self) -> Result<RgbaImage, Error> {
+		let RenderTarget::Texture(texture) = &self.target else {
+			return Err(Error::CaptureWindow);
+		};
+
+		let width = self.size.width;
+		let height = self.size.height;
+
+		// Rows in a texture copy must be padded to a multiple of 256 bytes
+		let unpadded_bytes_per_row = width * 4;
+		let padded_bytes_per_row = unpadded_bytes_per_row
+			.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+			* wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+		let buffer = self.device.create_buffer(&BufferDescriptor {
+			label: Some("Capture buffer"),
+			size: (padded_bytes_per_row * height) as u64,
+			usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+			mapped_at_creation: false,
+		});
+
+		let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
+			label: Some("Capture encoder"),
+		});
+
+		encoder.copy_texture_to_buffer(
+			texture.as_image_copy(),
+			TexelCopyBufferInfo {
+				buffer: &buffer,
+				layout: TexelCopyBufferLayout {
+					offset: 0,
+					bytes_per_row: Some(padded_bytes_per_row),
+					rows_per_image: Some(height),
+				},
+			},
+			texture.size(),
+		);
+
+		self.queue.submit(std::iter::once(encoder.finish()));
+
+		let slice = buffer.slice(..);
+		let (sender, receiver) = mpsc::channel();
+		slice.map_async(MapMode::Read, move |result| {
+			let _ = sender.send(result);
+		});
+		self.device.poll(PollType::Wait)?;
+		// The callback is dropped without being called if the device is lost
+		receiver.recv().unwrap_or(Err(BufferAsyncError))?;
+
+		let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+		{
+			let data = slice.get_mapped_range();
+			for row in data.chunks(padded_bytes_per_row as usize) {
+				pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+			}
+		}
+		buffer.unmap();
+
+		Ok(RgbaImage::from_raw(width, height, pixels).expect("the frame has one pixel per texel"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	#[ignore = "needs a GPU adapter, run with `cargo test -- --ignored`"]
+	fn capture_rendered_quad() {
+		let mut state = smol::block_on(State::headless(8, 8)).unwrap();
+
+		let mut ctx = DrawContext::new();
+		ctx.quad(Rect::new(0.0, 0.0, 4.0, 8.0), Color::RED);
+		state.draw_context(&mut ctx);
+		state.render().unwrap();
+
+		let image = state.capture().unwrap();
+		assert_eq!(image.dimensions(), (8, 8));
+		assert_eq!(image.get_pixel(1, 4).0, [255, 0, 0, 255]);
+		assert_eq!(image.get_pixel(6, 4).0, [255, 255, 255, 255]);
+	}
+
+	#[test]
+	#[ignore = "needs a GPU adapter, run with `cargo test -- --ignored`"]
+	fn encode_colors_for_linear_targets() {
+		let format = TextureFormat::Rgba8Unorm;
+		let mut state = smol::block_on(State::headless_with_format(4, 4, format)).unwrap();
+
+		let mut ctx = DrawContext::new();
+		ctx.quad(Rect::new(0.0, 0.0, 4.0, 4.0), Color::rgb8(128, 64, 200));
+		state.draw_context(&mut ctx);
+		state.render().unwrap();
+
+		let pixel = state.capture().unwrap().get_pixel(2, 2).0;
+		for (channel, expected) in pixel.into_iter().zip([128, 64, 200, 255]) {
+			assert!(channel.abs_diff(expected) <= 1, "{pixel:?}");
+		}
+	}
+
+	#[test]
+	#[ignore = "needs a GPU adapter, run with `cargo test -- --ignored`"]
+	fn shadow_is_cut_out_under_its_rect() {
+		let mut state = smol::block_on(State::headless(16, 16)).unwrap();
+
+		let shadow = BoxShadow::new(Position::ZERO, 4.0, 0.0, Color::BLACK);
+		let rect = RoundedRect::new(
+			Rect::new(4.0, 4.0, 8.0, 8.0),
+			CornerRadii::all(0.0),
+			Color::BLACK.with_alpha(0.0),
+		);
+		state.draw_rect(&rect.shadow(shadow));
+		state.render().unwrap();
+
+		let image = state.capture().unwrap();
+		assert_eq!(image.get_pixel(8, 8).0, [255, 255, 255, 255]);
+		assert!(image.get_pixel(2, 8).0[0] < 255);
+	}
+}
@@ -0,0 +1,267 @@
+use wgpu::IndexFormat;
+
+use crate::{Brush, Position, Rect, Vertex};
+
+/// The index buffer of a [`Mesh`].
+///
+/// Indices start out as `u16`, which halves the upload size, and are
+/// widened to `u32` once a mesh has more vertices than `u16` can address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Default for Indices {
+    fn default() -> Self {
+        Self::U16(Vec::new())
+    }
+}
+
+impl Indices {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U16(indices) => indices.len(),
+            Self::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the indices, widened to `u32`.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        let (short, long) = match self {
+            Self::U16(indices) => (indices.as_slice(), [].as_slice()),
+            Self::U32(indices) => ([].as_slice(), indices.as_slice()),
+        };
+        short.iter().map(|&i| i as u32).chain(long.iter().copied())
+    }
+
+    /// Adds an index, widening the indices to `u32` if it doesn't
+    /// fit in a `u16`.
+    pub fn push(&mut self, index: u32) {
+        match self {
+            Self::U16(indices) => match u16::try_from(index) {
+                Ok(index) => indices.push(index),
+                Err(_) => {
+                    let mut wide: Vec<u32> = indices.iter().map(|&i| i as u32).collect();
+                    wide.push(index);
+                    *self = Self::U32(wide);
+                }
+            },
+            Self::U32(indices) => indices.push(index),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            Self::U16(indices) => indices.clear(),
+            Self::U32(indices) => indices.clear(),
+        }
+    }
+
+    /// The format of the indices when bound as an index buffer.
+    pub fn format(&self) -> IndexFormat {
+        match self {
+            Self::U16(_) => IndexFormat::Uint16,
+            Self::U32(_) => IndexFormat::Uint32,
+        }
+    }
+
+    /// The raw bytes of the indices.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::U16(indices) => bytemuck::cast_slice(indices),
+            Self::U32(indices) => bytemuck::cast_slice(indices),
+        }
+    }
+}
+
+/// Indexed triangle geometry.
+///
+/// Shared corners are stored once, a quad is 4 vertices and 6 indices
+/// rather than 6 vertices.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Mesh {
+    vertices: Vec<Vertex>,
+    indices: Indices,
+}
+
+impl Mesh {
+    /// Creates an empty [`Mesh`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty [`Mesh`] with room for `vertices` vertices
+    /// and `indices` indices.
+    pub fn with_capacity(vertices: usize, indices: usize) -> Self {
+        Self {
+            vertices: Vec::with_capacity(vertices),
+            indices: Indices::U16(Vec::with_capacity(indices)),
+        }
+    }
+
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    pub(crate) fn vertices_mut(&mut self) -> &mut [Vertex] {
+        &mut self.vertices
+    }
+
+    pub fn indices(&self) -> &Indices {
+        &self.indices
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Removes all the geometry, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    /// Adds a vertex and returns its index.
+    pub fn push_vertex(&mut self, vertex: Vertex) -> u32 {
+        self.vertices.push(vertex);
+        self.vertices.len() as u32 - 1
+    }
+
+    /// Adds a triangle between three vertices that were already added.
+    pub fn push_triangle(&mut self, a: u32, b: u32, c: u32) {
+        debug_assert!(
+            [a, b, c]
+                .iter()
+                .all(|&i| (i as usize) < self.vertices.len()),
+            "triangle refers to a vertex that doesn't exist"
+        );
+        self.indices.push(a);
+        self.indices.push(b);
+        self.indices.push(c);
+    }
+
+    /// Adds vertices laid out as a triangle list.
+    pub fn push_triangles(&mut self, vertices: &[Vertex]) {
+        let start = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(vertices);
+        for i in 0..vertices.len() as u32 {
+            self.indices.push(start + i);
+        }
+    }
+
+    /// Adds a quad covering `rect`, as 4 vertices and 6 indices.
+    ///
+    /// Vertices only have one color each, so gradients are sampled at
+    /// the corners and blended between them, which only matches linear
+    /// gradients between two stops. [`DrawContext::quad`] draws other
+    /// gradients exactly.
+    ///
+    /// [`DrawContext::quad`]: crate::DrawContext::quad
+    pub fn push_quad(&mut self, rect: Rect, fill: impl Into<Brush>) {
+        let fill = fill.into();
+        let (center_x, center_y) = (
+            rect.x() + rect.width() / 2.0,
+            rect.y() + rect.height() / 2.0,
+        );
+        let mut corner = |x: f32, y: f32| {
+            let color = fill.color_at(Position::new(x - center_x, y - center_y), rect.size);
+            self.push_vertex(Vertex {
+                position: [x, y],
+                color,
+                uv: [1.0, 1.0],
+            })
+        };
+
+        let (x, y) = (rect.x(), rect.y());
+        let (right, bottom) = (rect.right(), rect.bottom());
+        let top_left = corner(x, y);
+        let top_right = corner(right, y);
+        let bottom_left = corner(x, bottom);
+        let bottom_right = corner(right, bottom);
+
+        self.push_triangle(top_left, top_right, bottom_left);
+        self.push_triangle(top_right, bottom_left, bottom_right);
+    }
+
+    /// Adds all the geometry of `other` to this mesh.
+    pub fn extend(&mut self, other: &Mesh) {
+        let offset = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&other.vertices);
+        for index in other.indices.iter() {
+            self.indices.push(index + offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, LinearGradient};
+
+    #[test]
+    fn quad_shares_corners() {
+        let mut mesh = Mesh::new();
+        mesh.push_quad(Rect::new(10.0, 20.0, 50.0, 75.0), Color::BLACK);
+
+        assert_eq!(mesh.vertices().len(), 4);
+        assert_eq!(mesh.indices(), &Indices::U16(vec![0, 1, 2, 1, 2, 3]));
+        assert_eq!(mesh.vertices()[0].position, [10.0, 20.0]);
+        assert_eq!(mesh.vertices()[3].position, [60.0, 95.0]);
+    }
+
+    #[test]
+    fn quad_samples_gradient_at_corners() {
+        let gradient = LinearGradient::new(0.0)
+            .stop(0.0, Color::BLACK)
+            .stop(1.0, Color::WHITE);
+        let mut mesh = Mesh::new();
+        mesh.push_quad(Rect::new(10.0, 20.0, 50.0, 75.0), gradient);
+
+        let colors: Vec<_> = mesh.vertices().iter().map(|vertex| vertex.color).collect();
+        let (black, white) = ([0.0, 0.0, 0.0, 1.0], [1.0; 4]);
+        assert_eq!(colors, [black, white, black, white]);
+    }
+
+    #[test]
+    fn extend_offsets_indices() {
+        let mut quad = Mesh::new();
+        quad.push_quad(Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+
+        let mut mesh = Mesh::new();
+        mesh.extend(&quad);
+        mesh.extend(&quad);
+
+        assert_eq!(mesh.vertices().len(), 8);
+        let indices: Vec<u32> = mesh.indices().iter().collect();
+        assert_eq!(indices, [0, 1, 2, 1, 2, 3, 4, 5, 6, 5, 6, 7]);
+    }
+
+    #[test]
+    fn widen_indices_past_u16() {
+        let mut mesh = Mesh::new();
+        for _ in 0..(u16::MAX as usize + 1) / 4 {
+            mesh.push_quad(Rect::new(0.0, 0.0, 1.0, 1.0), Color::BLACK);
+        }
+        assert_eq!(mesh.indices().format(), IndexFormat::Uint16);
+
+        mesh.push_quad(Rect::new(0.0, 0.0, 1.0, 1.0), Color::BLACK);
+        assert_eq!(mesh.indices().format(), IndexFormat::Uint32);
+        assert_eq!(mesh.indices().iter().last(), Some(u16::MAX as u32 + 4));
+        assert_eq!(mesh.indices().len(), (u16::MAX as usize + 1) / 4 * 6 + 6);
+    }
+
+    #[test]
+    fn triangles_are_indexed_in_order() {
+        let vertex = Vertex::new(0.0, 0.0, Color::BLACK);
+        let mut mesh = Mesh::new();
+        mesh.push_triangles(&[vertex; 6]);
+
+        let indices: Vec<u32> = mesh.indices().iter().collect();
+        assert_eq!(indices, [0, 1, 2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,933 @@
+use std::ops::Range;
+
+use unicode_linebreak::{BreakOpportunity, linebreaks};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    Color, Constraints, DrawContext, Font, Fonts, LayoutContext, LineMetrics, Position, Rect,
+    ShapedGlyph, Size, TextStyle, Widget,
+};
+
+/// How the lines of a [`Paragraph`] are aligned horizontally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches the spaces of every line but the last to fill the
+    /// width, lines ending in a line break are aligned left.
+    Justify,
+}
+
+/// A run of text drawn in a single style and color.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: TextStyle,
+    pub color: Color,
+}
+
+impl Span {
+    /// Creates a black span of `text`.
+    pub fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        Self {
+            text: text.into(),
+            style,
+            color: Color::BLACK,
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+/// Text made of styled spans, which is broken into lines by
+/// [`Paragraph::layout`].
+///
+/// Each span is shaped separately, so kerning and ligatures don't
+/// cross span boundaries. Lines are laid out left to right, right to
+/// left text is only reordered within a span.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Paragraph {
+    spans: Vec<Span>,
+    align: TextAlign,
+    max_lines: Option<usize>,
+}
+
+impl Paragraph {
+    /// Creates a paragraph of `text` in a single black span.
+    pub fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        Self::default().span(Span::new(text, style))
+    }
+
+    /// Appends a span to the end of the paragraph.
+    pub fn span(mut self, span: Span) -> Self {
+        self.spans.push(span);
+        self
+    }
+
+    pub fn align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Limits the paragraph to `max_lines`, ending the last line with
+    /// an ellipsis when the text is cut off.
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = Some(max_lines);
+        self
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    /// The text of all spans, which character indices refer to.
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    /// Breaks the paragraph into lines no wider than `max_width`, at
+    /// word boundaries where possible and between graphemes where a
+    /// word doesn't fit on a line by itself.
+    ///
+    /// Lines that aren't aligned left are aligned within `max_width`,
+    /// which the layout then fills. Use [`f32::INFINITY`] to only break
+    /// lines at line breaks and align them within the widest line.
+    pub fn layout(&self, fonts: &Fonts, max_width: f32) -> ParagraphLayout {
+        let text = self.text();
+        let units = self.units(fonts, &text);
+        let mut lines = break_lines(&text, &units, max_width);
+
+        let max_lines = self.max_lines.map(|max| max.max(1));
+        let truncated = max_lines.is_some_and(|max| lines.len() > max);
+        if let Some(max) = max_lines.filter(|_| truncated) {
+            lines.truncate(max);
+            let last = lines.last_mut().unwrap();
+            let span = units[..last.units.end]
+                .last()
+                .map_or(self.spans.len().saturating_sub(1), |unit| unit.span);
+            let style = self.spans.get(span).map(|span| span.style);
+            let shaped = fonts.shape("\u{2026}", &style.unwrap_or_default());
+
+            // Drop whole graphemes until the ellipsis fits
+            last.mandatory = false;
+            last.units.end = last.units.start + content_len(&units[last.units.clone()]);
+            while last.units.end > last.units.start
+                && content_width(&units[last.units.clone()]) + shaped.width() > max_width
+            {
+                last.units.end -= 1;
+                last.units.end = last.units.start + content_len(&units[last.units.clone()]);
+            }
+
+            // The ellipsis replaces the text after the last unit kept
+            let end = units[last.units.clone()]
+                .last()
+                .map_or(last.start, |unit| unit.range.end);
+            last.ellipsis = Some(Unit {
+                range: end..end,
+                span,
+                advance: shaped.width(),
+                glyphs: shaped.glyphs().iter().map(|glyph| (span, *glyph)).collect(),
+                rtl: false,
+                whitespace: false,
+            });
+        }
+
+        let widths: Vec<f32> = lines.iter().map(|line| line.width(&units)).collect();
+        let mut width = widths.iter().copied().fold(0.0, f32::max);
+        if self.align != TextAlign::Left && max_width.is_finite() {
+            width = width.max(max_width);
+        }
+
+        let mut layout = ParagraphLayout {
+            text,
+            lines: Vec::with_capacity(lines.len()),
+            runs: Vec::new(),
+            size: Size::new(width, 0.0),
+            truncated,
+        };
+        let line_count = lines.len();
+        for (i, (line, line_width)) in lines.iter().zip(widths).enumerate() {
+            let justify = self.align == TextAlign::Justify && !line.mandatory && i + 1 < line_count;
+            let offset = match self.align {
+                TextAlign::Left | TextAlign::Justify => 0.0,
+                TextAlign::Center => (width - line_width) / 2.0,
+                TextAlign::Right => width - line_width,
+            };
+            let spacing = if justify { width - line_width } else { 0.0 };
+            self.place_line(fonts, &mut layout, &units, line, offset, spacing);
+        }
+        layout
+    }
+
+    /// Shapes every span and splits the result into units that lines
+    /// can be broken between, in logical order.
+    fn units(&self, fonts: &Fonts, text: &str) -> Vec<Unit> {
+        let mut boundaries = vec![false; text.len() + 1];
+        for (index, _) in text.grapheme_indices(true) {
+            boundaries[index] = true;
+        }
+        boundaries[text.len()] = true;
+
+        let mut units: Vec<Unit> = Vec::new();
+        let mut partial: Option<Unit> = None;
+        let mut start = 0;
+        for (span_index, span) in self.spans.iter().enumerate() {
+            let shaped = fonts.shape(&span.text, &span.style);
+            let glyphs = shaped.glyphs();
+            let rtl = glyphs.len() > 1 && glyphs[0].cluster > glyphs[glyphs.len() - 1].cluster;
+
+            // Group the glyphs of each cluster, in visual order, with
+            // positions relative to the start of the cluster
+            let mut clusters = Vec::new();
+            let mut pen = 0.0;
+            for glyph in glyphs {
+                match clusters.last_mut() {
+                    Some((cluster, _, _)) if *cluster == glyph.cluster => {}
+                    _ => clusters.push((glyph.cluster, pen, Vec::new())),
+                }
+                let (_, left, cluster) = clusters.last_mut().unwrap();
+                let x = glyph.x - *left;
+                cluster.push((span_index, ShapedGlyph { x, ..*glyph }));
+                pen += glyph.advance;
+            }
+            if rtl {
+                clusters.reverse();
+            }
+
+            for i in 0..clusters.len() {
+                let end = clusters.get(i + 1).map_or(span.text.len(), |next| next.0);
+                let (cluster, _, glyphs) = &clusters[i];
+                let range = start + cluster..start + end;
+                let advance = glyphs.iter().map(|(_, glyph)| glyph.advance).sum();
+                let whitespace = text[range.clone()].chars().all(char::is_whitespace);
+                let mut unit = Unit {
+                    range,
+                    span: span_index,
+                    advance,
+                    glyphs: glyphs.clone(),
+                    rtl,
+                    whitespace,
+                };
+                if text[unit.range.clone()].chars().any(is_line_break) {
+                    unit.advance = 0.0;
+                    unit.glyphs.clear();
+                }
+
+                let unit = match partial.take() {
+                    Some(partial) => partial.join(unit),
+                    None => unit,
+                };
+                if boundaries[unit.range.end] {
+                    units.push(unit);
+                } else {
+                    partial = Some(unit);
+                }
+            }
+            start += span.text.len();
+        }
+        units.extend(partial);
+        units
+    }
+
+    /// Positions the glyphs and carets of `line` below the lines
+    /// already in `layout`.
+    fn place_line(
+        &self,
+        fonts: &Fonts,
+        layout: &mut ParagraphLayout,
+        units: &[Unit],
+        line: &LineBreak,
+        offset: f32,
+        spacing: f32,
+    ) {
+        let line_units = &units[line.units.clone()];
+        let content = content_len(line_units);
+
+        // Empty lines take the style of the text before them
+        let span = line_units
+            .first()
+            .or(units[..line.units.start].last())
+            .map_or(0, |unit| unit.span);
+        let span_metrics = |span: &Span| fonts.select(span.style.weight).line_metrics(&span.style);
+        let mut metrics = self.spans.get(span).map(span_metrics).unwrap_or_default();
+        for unit in line_units.iter().chain(&line.ellipsis) {
+            let other = span_metrics(&self.spans[unit.span]);
+            metrics.ascent = metrics.ascent.max(other.ascent);
+            metrics.descent = metrics.descent.max(other.descent);
+            metrics.line_height = metrics.line_height.max(other.line_height);
+        }
+
+        let y = layout.size.height;
+        let baseline = y + metrics.baseline();
+        let spaces = line_units[..content]
+            .iter()
+            .filter(|unit| unit.whitespace)
+            .count();
+        let extra = if spaces > 0 {
+            spacing / spaces as f32
+        } else {
+            0.0
+        };
+        let advance = |unit: &Unit| unit.advance + if unit.whitespace { extra } else { 0.0 };
+
+        // The left edge of each unit, reversing runs of right to left text
+        let mut lefts = vec![0.0; line_units.len()];
+        let mut x = offset;
+        let mut i = 0;
+        while i < line_units.len() {
+            let rtl = line_units[i].rtl;
+            let end = line_units[i..]
+                .iter()
+                .position(|unit| unit.rtl != rtl)
+                .map_or(line_units.len(), |len| i + len);
+            let run_width: f32 = line_units[i..end].iter().map(advance).sum();
+            let mut pen = if rtl { x + run_width } else { x };
+            for (unit, left) in line_units[i..end].iter().zip(&mut lefts[i..end]) {
+                if rtl {
+                    pen -= advance(unit);
+                    *left = pen;
+                } else {
+                    *left = pen;
+                    pen += advance(unit);
+                }
+            }
+            x += run_width;
+            i = end;
+        }
+
+        let mut carets = Vec::new();
+        for (unit, &left) in line_units.iter().zip(&lefts) {
+            let graphemes: Vec<usize> = layout.text[unit.range.clone()]
+                .grapheme_indices(true)
+                .map(|(index, _)| unit.range.start + index)
+                .collect();
+            // Ligatures are split evenly between their graphemes
+            for (k, &index) in graphemes.iter().enumerate() {
+                let fraction = k as f32 / graphemes.len() as f32;
+                let fraction = if unit.rtl { 1.0 - fraction } else { fraction };
+                carets.push((index, left + unit.advance * fraction));
+            }
+        }
+        let end_x = match line_units[..content].last().zip(lefts[..content].last()) {
+            Some((unit, &left)) if unit.rtl => left,
+            Some((unit, &left)) => left + unit.advance,
+            None => offset,
+        };
+        let end = line_units.last().map_or(line.start, |unit| unit.range.end);
+        let ends_in_break = line_units
+            .last()
+            .is_some_and(|unit| layout.text[unit.range.clone()].chars().any(is_line_break));
+        if line.ellipsis.is_some() {
+            carets.push((end, end_x));
+        } else if !ends_in_break {
+            carets.push((end, x));
+        }
+
+        let placed = line_units
+            .iter()
+            .zip(lefts)
+            .chain(line.ellipsis.as_ref().map(|unit| (unit, end_x)));
+        for (unit, left) in placed {
+            for &(span, glyph) in &unit.glyphs {
+                let glyph = ShapedGlyph {
+                    x: left + glyph.x,
+                    y: baseline + glyph.y,
+                    ..glyph
+                };
+                match layout.runs.last_mut() {
+                    Some(run) if run.span == span => run.glyphs.push(glyph),
+                    _ => layout.runs.push(GlyphRun {
+                        span,
+                        font: fonts.select(self.spans[span].style.weight).clone(),
+                        style: self.spans[span].style,
+                        color: self.spans[span].color,
+                        glyphs: vec![glyph],
+                    }),
+                }
+            }
+        }
+
+        layout.lines.push(Line {
+            range: line_units
+                .first()
+                .map_or(line.start, |unit| unit.range.start)..end,
+            x: offset,
+            y,
+            width: end_x - offset,
+            metrics,
+            carets,
+        });
+        layout.size.height += metrics.line_height;
+    }
+}
+
+/// A widget that lays out a [`Paragraph`] with the window's fonts,
+/// wrapping it to the width it's given.
+#[derive(Debug, Clone)]
+pub struct Text {
+    paragraph: Paragraph,
+    layout: Option<ParagraphLayout>,
+}
+
+impl Text {
+    /// Creates a widget with a single span of `text` in `style`.
+    pub fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        Paragraph::new(text, style).into()
+    }
+
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+}
+
+impl From<Paragraph> for Text {
+    fn from(paragraph: Paragraph) -> Self {
+        Self {
+            paragraph,
+            layout: None,
+        }
+    }
+}
+
+impl Widget for Text {
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        let layout = self.paragraph.layout(ctx.fonts(), constraints.max.width);
+        let size = layout.size();
+        self.layout = Some(layout);
+        size
+    }
+
+    fn draw(&self, ctx: &mut DrawContext) {
+        if let Some(layout) = &self.layout {
+            let position = ctx.bounds().position;
+            ctx.paragraph(layout, position);
+        }
+    }
+}
+
+/// Whether `c` forces a line break.
+fn is_line_break(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// A grapheme, or a ligature spanning several, that a line can't be
+/// broken within.
+#[derive(Debug, Clone)]
+struct Unit {
+    /// The byte range in the text of the paragraph.
+    range: Range<usize>,
+    span: usize,
+    advance: f32,
+    /// The glyphs and the span they're from, positioned from the left
+    /// edge of the unit.
+    glyphs: Vec<(usize, ShapedGlyph)>,
+    rtl: bool,
+    whitespace: bool,
+}
+
+impl Unit {
+    /// Merges a cluster that follows this one in the text.
+    fn join(mut self, next: Unit) -> Unit {
+        let (shift, next_shift) = if next.rtl {
+            (next.advance, 0.0)
+        } else {
+            (0.0, self.advance)
+        };
+        for (_, glyph) in &mut self.glyphs {
+            glyph.x += shift;
+        }
+        self.glyphs
+            .extend(next.glyphs.into_iter().map(|(span, glyph)| {
+                (
+                    span,
+                    ShapedGlyph {
+                        x: glyph.x + next_shift,
+                        ..glyph
+                    },
+                )
+            }));
+        self.range.end = next.range.end;
+        self.advance += next.advance;
+        self.whitespace &= next.whitespace;
+        self
+    }
+}
+
+/// The number of units before trailing whitespace, which hangs past
+/// the end of a line.
+fn content_len(units: &[Unit]) -> usize {
+    units
+        .iter()
+        .rposition(|unit| !unit.whitespace)
+        .map_or(0, |i| i + 1)
+}
+
+/// The width of `units` without trailing whitespace.
+fn content_width(units: &[Unit]) -> f32 {
+    units[..content_len(units)]
+        .iter()
+        .map(|unit| unit.advance)
+        .sum()
+}
+
+/// The units on a line, before alignment.
+#[derive(Debug, Clone)]
+struct LineBreak {
+    units: Range<usize>,
+    /// The text index where the line starts, for empty lines.
+    start: usize,
+    /// Whether the line ends in a line break or the end of the text.
+    mandatory: bool,
+    ellipsis: Option<Unit>,
+}
+
+impl LineBreak {
+    fn new(units: Range<usize>, start: usize, mandatory: bool) -> Self {
+        Self {
+            units,
+            start,
+            mandatory,
+            ellipsis: None,
+        }
+    }
+
+    fn width(&self, units: &[Unit]) -> f32 {
+        let ellipsis = self.ellipsis.as_ref().map_or(0.0, |unit| unit.advance);
+        content_width(&units[self.units.clone()]) + ellipsis
+    }
+}
+
+/// Breaks `units` into lines no wider than `max_width`, filling each
+/// line with as many words as fit.
+fn break_lines(text: &str, units: &[Unit], max_width: f32) -> Vec<LineBreak> {
+    let mut breaks = vec![None; text.len() + 1];
+    for (index, opportunity) in linebreaks(text) {
+        breaks[index] = Some(opportunity);
+    }
+
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    let mut line_width = 0.0;
+    let mut word_start = 0;
+    let start_of = |unit: usize| units.get(unit).map_or(text.len(), |unit| unit.range.start);
+
+    for (i, unit) in units.iter().enumerate() {
+        let opportunity = breaks[unit.range.end];
+        if opportunity.is_none() && i + 1 < units.len() {
+            continue;
+        }
+        let word = word_start..i + 1;
+        word_start = i + 1;
+
+        let width = content_width(&units[word.clone()]);
+        if line_start < word.start && line_width + width > max_width {
+            lines.push(LineBreak::new(
+                line_start..word.start,
+                start_of(line_start),
+                false,
+            ));
+            line_start = word.start;
+            line_width = 0.0;
+        }
+        if width > max_width {
+            // The word doesn't fit on a line of its own
+            for j in word.clone() {
+                let unit = &units[j];
+                if line_start < j && !unit.whitespace && line_width + unit.advance > max_width {
+                    lines.push(LineBreak::new(line_start..j, start_of(line_start), false));
+                    line_start = j;
+                    line_width = 0.0;
+                }
+                line_width += unit.advance;
+            }
+        } else {
+            line_width += units[word.clone()]
+                .iter()
+                .map(|unit| unit.advance)
+                .sum::<f32>();
+        }
+
+        if opportunity == Some(BreakOpportunity::Mandatory) {
+            lines.push(LineBreak::new(
+                line_start..word.end,
+                start_of(line_start),
+                true,
+            ));
+            line_start = word.end;
+            line_width = 0.0;
+        }
+    }
+
+    // Text ending in a line break, or no text at all, ends with an
+    // empty line for the caret
+    if line_start < units.len() {
+        lines.push(LineBreak::new(
+            line_start..units.len(),
+            start_of(line_start),
+            true,
+        ));
+    } else if lines.is_empty()
+        || units
+            .last()
+            .is_some_and(|unit| text[unit.range.clone()].chars().any(is_line_break))
+    {
+        lines.push(LineBreak::new(units.len()..units.len(), text.len(), true));
+    }
+    lines
+}
+
+/// A paragraph broken into lines and positioned, with the top left of
+/// the paragraph at the origin.
+#[derive(Debug, Clone)]
+pub struct ParagraphLayout {
+    text: String,
+    lines: Vec<Line>,
+    pub(crate) runs: Vec<GlyphRun>,
+    size: Size,
+    truncated: bool,
+}
+
+/// Consecutive glyphs from the same span, positioned on their
+/// baseline relative to the paragraph.
+#[derive(Debug, Clone)]
+pub(crate) struct GlyphRun {
+    pub span: usize,
+    pub font: Font,
+    pub style: TextStyle,
+    pub color: Color,
+    pub glyphs: Vec<ShapedGlyph>,
+}
+
+impl ParagraphLayout {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// The width of the widest line and the height of all lines,
+    /// which lines are aligned within.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Whether text was left out to fit in the maximum number of lines.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// The index of the line containing the character at `index`.
+    ///
+    /// An index where a line wraps is on the line after the wrap.
+    pub fn line_at(&self, index: usize) -> usize {
+        self.lines
+            .iter()
+            .rposition(|line| line.range.start <= index)
+            .unwrap_or(0)
+    }
+
+    /// Where to draw a caret before the character at the byte `index`,
+    /// rounded down to a grapheme boundary.
+    pub fn caret(&self, index: usize) -> Caret {
+        let line_index = self.line_at(index);
+        let line = &self.lines[line_index];
+        let x = line
+            .carets
+            .iter()
+            .take_while(|&&(caret, _)| caret <= index)
+            .last()
+            .or(line.carets.first())
+            .map_or(line.x, |&(_, x)| x);
+
+        Caret {
+            position: Position::new(x, line.y),
+            height: line.metrics.line_height,
+            line: line_index,
+        }
+    }
+
+    /// The byte index of the caret position closest to `point`, for
+    /// placing the caret where the text was clicked.
+    pub fn index_at(&self, point: Position) -> usize {
+        let line = self
+            .lines
+            .iter()
+            .find(|line| point.y < line.y + line.metrics.line_height)
+            .or(self.lines.last());
+        let Some(line) = line else {
+            return 0;
+        };
+
+        line.carets
+            .iter()
+            .min_by(|a, b| (a.1 - point.x).abs().total_cmp(&(b.1 - point.x).abs()))
+            .map_or(line.range.start, |&(index, _)| index)
+    }
+}
+
+/// A laid out line of a [`ParagraphLayout`].
+#[derive(Debug, Clone)]
+pub struct Line {
+    range: Range<usize>,
+    x: f32,
+    y: f32,
+    width: f32,
+    metrics: LineMetrics,
+    /// The byte index and x position of each caret position, in
+    /// logical order.
+    carets: Vec<(usize, f32)>,
+}
+
+impl Line {
+    /// The byte range of the text on this line, including trailing
+    /// whitespace and line breaks.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// The bounds of the line, without trailing whitespace.
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.metrics.line_height)
+    }
+
+    /// The ascent and descent of the tallest span on the line.
+    pub fn metrics(&self) -> LineMetrics {
+        self.metrics
+    }
+
+    /// The distance from the top of the paragraph to the baseline.
+    pub fn baseline(&self) -> f32 {
+        self.y + self.metrics.baseline()
+    }
+}
+
+/// The position of a text caret, see [`ParagraphLayout::caret`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Caret {
+    /// The top of the caret.
+    pub position: Position,
+    pub height: f32,
+    /// The index of the line the caret is on.
+    pub line: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WidgetNode;
+    use crate::text::tests::fonts;
+
+    fn line_texts(layout: &ParagraphLayout) -> Vec<&str> {
+        let text = layout.text();
+        layout
+            .lines()
+            .iter()
+            .map(|line| &text[line.range()])
+            .collect()
+    }
+
+    #[test]
+    fn wrap_at_words() {
+        let fonts = fonts();
+        let style = TextStyle::new(16.0);
+        let width = fonts.measure("Hello world,", &style).width;
+        let layout = Paragraph::new("Hello world, hello again", style).layout(&fonts, width);
+
+        assert_eq!(line_texts(&layout), ["Hello world, ", "hello again"]);
+        assert!(layout.lines().iter().all(|line| line.rect().width() <= width));
+        assert_eq!(
+            layout.lines()[1].rect().y(),
+            layout.lines()[0].metrics().line_height
+        );
+        assert_eq!(
+            layout.size().height,
+            layout.lines()[0].metrics().line_height * 2.0
+        );
+    }
+
+    #[test]
+    fn break_long_words_between_graphemes() {
+        let fonts = fonts();
+        let style = TextStyle::new(16.0);
+        // Every "e" has a combining accent, which stays with its letter
+        let word = "de\u{301}ja\u{300}de\u{301}ja\u{300}de\u{301}ja\u{300}";
+        let width = fonts.measure("dej", &style).width;
+        let layout = Paragraph::new(word, style).layout(&fonts, width);
+
+        assert!(layout.lines().len() > 2);
+        for line in line_texts(&layout) {
+            assert!(!line.starts_with(['\u{301}', '\u{300}']), "{line:?}");
+        }
+        assert_eq!(line_texts(&layout).concat(), word);
+    }
+
+    #[test]
+    fn line_breaks() {
+        let layout =
+            Paragraph::new("one\n\ntwo\n", TextStyle::new(16.0)).layout(&fonts(), f32::INFINITY);
+
+        assert_eq!(line_texts(&layout), ["one\n", "\n", "two\n", ""]);
+        assert_eq!(layout.caret(9).line, 3);
+        let empty = Paragraph::new("", TextStyle::new(16.0)).layout(&fonts(), 100.0);
+        assert_eq!(empty.lines().len(), 1);
+        assert!(empty.size().height > 0.0);
+    }
+
+    #[test]
+    fn align_lines() {
+        let fonts = fonts();
+        let style = TextStyle::new(16.0);
+        let paragraph = Paragraph::new("A wide line\nnarrow", style);
+
+        let layout = paragraph
+            .clone()
+            .align(TextAlign::Right)
+            .layout(&fonts, f32::INFINITY);
+        let [wide, narrow] = [0, 1].map(|i| layout.lines()[i].rect());
+        assert_eq!(wide.x(), 0.0);
+        assert!((narrow.right() - wide.right()).abs() < 1e-3);
+
+        let layout = paragraph
+            .clone()
+            .align(TextAlign::Center)
+            .layout(&fonts, f32::INFINITY);
+        let narrow = layout.lines()[1].rect();
+        assert!((narrow.x() - (wide.width() - narrow.width()) / 2.0).abs() < 1e-3);
+
+        let width = 200.0;
+        let layout = paragraph
+            .clone()
+            .align(TextAlign::Right)
+            .layout(&fonts, width);
+        assert_eq!(layout.size().width, width);
+        for line in layout.lines() {
+            assert!((line.rect().right() - width).abs() < 1e-3);
+        }
+
+        let layout = paragraph.align(TextAlign::Center).layout(&fonts, width);
+        for line in layout.lines() {
+            let rect = line.rect();
+            assert!((rect.x() - (width - rect.width()) / 2.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn justify_all_but_the_last_line() {
+        let fonts = fonts();
+        let style = TextStyle::new(16.0);
+        let width = fonts.measure("aaa bbb ccc", &style).width + 10.0;
+        let layout = Paragraph::new("aaa bbb ccc dd e f gggg", style)
+            .align(TextAlign::Justify)
+            .layout(&fonts, width);
+
+        let lines = layout.lines();
+        assert!(lines.len() >= 2);
+        assert_eq!(layout.size().width, width);
+        for line in &lines[..lines.len() - 1] {
+            assert!((line.rect().width() - width).abs() < 1e-3);
+        }
+        assert!(lines.last().unwrap().rect().width() < width);
+    }
+
+    #[test]
+    fn text_widget_wraps_to_constraints() {
+        let fonts = fonts();
+        let style = TextStyle::new(16.0);
+        let measured = fonts.measure("Hello world", &style);
+
+        let mut node = WidgetNode::new(Text::new("Hello world", style));
+        let size = node.layout(Constraints::loose(Size::new(500.0, 500.0)), &fonts);
+        assert_eq!(size.width, measured.width);
+
+        let width = fonts.measure("Hello", &style).width;
+        let size = node.layout(Constraints::loose(Size::new(width, 500.0)), &fonts);
+        assert!(size.width <= width);
+        assert!(size.height > measured.height);
+    }
+
+    #[test]
+    fn truncate_with_ellipsis() {
+        let fonts = fonts();
+        let style = TextStyle::new(16.0);
+        let width = fonts.measure("Hello world", &style).width;
+        let layout = Paragraph::new("Hello world hello world hello world", style)
+            .max_lines(2)
+            .layout(&fonts, width);
+
+        assert!(layout.is_truncated());
+        assert_eq!(layout.lines().len(), 2);
+        assert!(layout.size().width <= width);
+        let ellipsis = fonts.shape("\u{2026}", &style).glyphs()[0].id;
+        let last = layout.runs.last().unwrap().glyphs.last().unwrap();
+        assert_eq!(last.id, ellipsis);
+        // Carets stop before the ellipsis
+        let end = layout.lines()[1].range().end;
+        assert_eq!(layout.index_at(Position::new(1000.0, 30.0)), end);
+    }
+
+    #[test]
+    fn mixed_styles() {
+        let fonts = fonts();
+        let small = TextStyle::new(12.0);
+        let large = TextStyle::new(24.0);
+        let layout = Paragraph::default()
+            .span(Span::new("small ", small))
+            .span(Span::new("large", large).color(Color::RED))
+            .layout(&fonts, f32::INFINITY);
+
+        let line = &layout.lines()[0];
+        assert_eq!(
+            line.metrics(),
+            fonts.select(large.weight).line_metrics(&large)
+        );
+        assert_eq!(layout.runs.len(), 2);
+        assert_eq!(layout.runs[1].color, Color::RED);
+        // Both spans share the baseline
+        let [a, b] = [0, 1].map(|i| layout.runs[i].glyphs[0].y);
+        assert_eq!(a, b);
+        assert_eq!(a, line.baseline());
+    }
+
+    #[test]
+    fn caret_round_trip() {
+        let fonts = fonts();
+        let style = TextStyle::new(16.0);
+        let width = fonts.measure("Hello wörld,", &style).width;
+        let layout = Paragraph::new("Hello wörld, hello again", style).layout(&fonts, width);
+
+        let mut last = Position::ZERO;
+        for (index, _) in layout.text().char_indices() {
+            let caret = layout.caret(index);
+            assert!(caret.position.y > last.y || caret.position.x > last.x || index == 0);
+            last = caret.position;
+
+            let point = caret.position.translate(0.1, caret.height / 2.0);
+            assert_eq!(layout.index_at(point), index);
+        }
+        let end = layout.caret(layout.text().len());
+        assert_eq!(end.line, 1);
+    }
+
+    #[test]
+    fn split_ligature_carets() {
+        let fonts = fonts();
+        let style = TextStyle::new(16.0);
+        let layout = Paragraph::new("ffi", style).layout(&fonts, f32::INFINITY);
+        let width = layout.size().width;
+
+        let x: Vec<f32> = (0..=3).map(|i| layout.caret(i).position.x).collect();
+        assert_eq!(x[0], 0.0);
+        assert!((x[1] - width / 3.0).abs() < 1e-3);
+        assert!((x[3] - width).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,65 @@
+// Definitions shared by every shader, which are prepended to each of them
+
+struct Globals {
+    // Maps logical pixels, with the origin at the top left, to clip space
+    projection: mat4x4<f32>,
+    scale_factor: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+
+struct Clip {
+    // The clip rectangle and its radii, in the coordinates it was pushed in
+    rect: vec4<f32>,
+    radii: vec4<f32>,
+    // Maps logical pixels back to the coordinates of the clip
+    inverse: vec4<f32>,
+    translation: vec2<f32>,
+    enabled: u32,
+};
+
+@group(1) @binding(0)
+var<uniform> clip: Clip;
+
+// Whether the target stores colors as they are rather than encoding
+// them to sRGB, in which case the shaders encode them
+override ENCODE_SRGB: bool = false;
+
+// The signed distance from `p` to the edge of a rectangle centered at the
+// origin, negative inside. Radii are top left, top right, bottom right
+// and bottom left.
+fn rounded_rect_sdf(p: vec2<f32>, half_size: vec2<f32>, radii: vec4<f32>) -> f32 {
+    var radius: f32;
+    if p.x < 0.0 {
+        radius = select(radii.w, radii.x, p.y < 0.0);
+    } else {
+        radius = select(radii.z, radii.y, p.y < 0.0);
+    }
+    radius = clamp(radius, 0.0, min(half_size.x, half_size.y));
+
+    let q = abs(p) - half_size + radius;
+    return min(max(q.x, q.y), 0.0) + length(max(q, vec2<f32>(0.0))) - radius;
+}
+
+// How much of the pixel at `position`, in physical pixels, is inside the
+// clip shape. Must be called from uniform control flow.
+fn clip_coverage(position: vec2<f32>) -> f32 {
+    let inverse = mat2x2<f32>(clip.inverse.xy, clip.inverse.zw);
+    let p = inverse * (position / globals.scale_factor) + clip.translation;
+    let half_size = clip.rect.zw * 0.5;
+    let distance = rounded_rect_sdf(p - clip.rect.xy - half_size, half_size, clip.radii);
+    let coverage = clamp(0.5 - distance / max(fwidth(distance), 1e-5), 0.0, 1.0);
+    return select(1.0, coverage, clip.enabled != 0u);
+}
+
+// Encodes a straight linear color for the target, see `ENCODE_SRGB`
+fn output_color(color: vec4<f32>) -> vec4<f32> {
+    if !ENCODE_SRGB {
+        return color;
+    }
+    let c = clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return vec4<f32>(select(high, low, c <= vec3<f32>(0.0031308)), color.a);
+}
@@ -0,0 +1,232 @@
+struct VertexInput {
+    // A corner of the unit quad
+    @location(0) corner: vec2<f32>,
+};
+
+struct InstanceInput {
+    // The position and size of the rectangle
+    @location(1) rect: vec4<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) radii: vec4<f32>,
+    @location(4) border_color: vec4<f32>,
+    @location(5) outline_color: vec4<f32>,
+    // Border width, outline width, outline offset and shadow blur
+    @location(6) params: vec4<f32>,
+    // Brush kind, first gradient stop and number of stops
+    @location(7) brush: vec4<u32>,
+    @location(8) brush_params: vec4<f32>,
+    // How far the quad extends past the rectangle
+    @location(9) extent: f32,
+    // The linear part of the transform and the translation after it
+    @location(10) transform: vec4<f32>,
+    @location(11) translation: vec2<f32>,
+    // The center and half size of the rectangle cut out of this one
+    @location(12) cutout: vec4<f32>,
+    @location(13) cutout_radii: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) local: vec2<f32>,
+    @location(2) half_size: vec2<f32>,
+    @location(3) radii: vec4<f32>,
+    @location(4) border_color: vec4<f32>,
+    @location(5) outline_color: vec4<f32>,
+    @location(6) params: vec4<f32>,
+    @location(7) @interpolate(flat) brush: vec4<u32>,
+    @location(8) @interpolate(flat) brush_params: vec4<f32>,
+    @location(9) @interpolate(flat) cutout: vec4<f32>,
+    @location(10) @interpolate(flat) cutout_radii: vec4<f32>,
+};
+
+struct GradientStop {
+    color: vec4<f32>,
+    offset: f32,
+};
+
+@group(2) @binding(0)
+var<storage, read> stops: array<GradientStop>;
+
+const SOLID: u32 = 0u;
+const LINEAR: u32 = 1u;
+const RADIAL: u32 = 2u;
+const CONIC: u32 = 3u;
+
+@vertex
+fn vs_main(vertex: VertexInput, in: InstanceInput) -> VertexOutput {
+    let half_size = in.rect.zw * 0.5;
+    let local = (vertex.corner * 2.0 - 1.0) * (half_size + in.extent);
+
+    let transform = mat2x2<f32>(in.transform.xy, in.transform.zw);
+    let position = transform * (in.rect.xy + half_size + local) + in.translation;
+
+    var out: VertexOutput;
+    out.position = globals.projection * vec4<f32>(position, 0.0, 1.0);
+    out.color = in.color;
+    out.local = local;
+    out.half_size = half_size;
+    out.radii = in.radii;
+    out.border_color = in.border_color;
+    out.outline_color = in.outline_color;
+    out.params = in.params;
+    out.brush = in.brush;
+    out.brush_params = in.brush_params;
+    out.cutout = in.cutout;
+    out.cutout_radii = in.cutout_radii;
+    return out;
+}
+
+// How much of the pixel is inside the edge at `distance`, fading out
+// over roughly one pixel.
+fn coverage(distance: f32, width: f32) -> f32 {
+    return clamp(0.5 - distance / width, 0.0, 1.0);
+}
+
+fn gaussian(x: f32, sigma: f32) -> f32 {
+    let pi = 3.141592653589793;
+    return exp(-(x * x) / (2.0 * sigma * sigma)) / (sqrt(2.0 * pi) * sigma);
+}
+
+// An approximation of the error function, accurate to about 5e-4
+fn erf(x: vec2<f32>) -> vec2<f32> {
+    let s = sign(x);
+    let a = abs(x);
+    var r = 1.0 + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    r = r * r;
+    return s - s / (r * r);
+}
+
+// The blurred coverage along the x axis of a row of the rectangle `y`
+// away from its center, which is exact for the straight edges
+fn shadow_row(x: f32, y: f32, sigma: f32, radius: f32, half_size: vec2<f32>) -> f32 {
+    let delta = min(half_size.y - radius - abs(y), 0.0);
+    let curved = half_size.x - radius + sqrt(max(0.0, radius * radius - delta * delta));
+    let integral = 0.5 + 0.5 * erf((x + vec2<f32>(-curved, curved)) * (sqrt(0.5) / sigma));
+    return integral.y - integral.x;
+}
+
+// The coverage of a rounded rectangle convolved with a gaussian. The
+// x axis is integrated exactly and the y axis is sampled, see
+// https://madebyevan.com/shaders/fast-rounded-rectangle-shadows/
+fn shadow_coverage(p: vec2<f32>, half_size: vec2<f32>, radii: vec4<f32>, sigma: f32) -> f32 {
+    var radius: f32;
+    if p.x < 0.0 {
+        radius = select(radii.w, radii.x, p.y < 0.0);
+    } else {
+        radius = select(radii.z, radii.y, p.y < 0.0);
+    }
+    radius = clamp(radius, 0.0, min(half_size.x, half_size.y));
+
+    let low = p.y - half_size.y;
+    let high = p.y + half_size.y;
+    let start = clamp(-3.0 * sigma, low, high);
+    let end = clamp(3.0 * sigma, low, high);
+
+    let samples = 4;
+    let step = (end - start) / f32(samples);
+    var y = start + step * 0.5;
+    var value = 0.0;
+    for (var i = 0; i < samples; i++) {
+        value += shadow_row(p.x, p.y - y, sigma, radius, half_size) * gaussian(y, sigma) * step;
+        y += step;
+    }
+    return value;
+}
+
+fn premultiply(color: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
+// The premultiplied color of the brush at `p`, relative to the center
+// of the rectangle
+fn brush_color(p: vec2<f32>, color: vec4<f32>, brush: vec4<u32>, params: vec4<f32>) -> vec4<f32> {
+    let kind = brush.x;
+    let first = brush.y;
+    let count = brush.z;
+    if kind == SOLID || count == 0u {
+        return premultiply(color);
+    }
+
+    var t: f32;
+    switch kind {
+        case LINEAR: {
+            t = dot(p, params.xy) + 0.5;
+        }
+        case RADIAL: {
+            t = length(p - params.xy) * params.z;
+        }
+        default: {
+            let tau = 6.283185307179586;
+            let d = p - params.xy;
+            t = fract((atan2(d.y, d.x) - params.z) / tau + 1.0);
+        }
+    }
+
+    var previous = stops[first];
+    if t <= previous.offset {
+        return premultiply(previous.color);
+    }
+    for (var i = 1u; i < count; i++) {
+        let next = stops[first + i];
+        if t <= next.offset {
+            let f = (t - previous.offset) / max(next.offset - previous.offset, 1e-6);
+            return mix(premultiply(previous.color), premultiply(next.color), f);
+        }
+        previous = next;
+    }
+    return premultiply(previous.color);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Derivatives are only defined in uniform control flow, so the
+    // clip and the anti-aliasing width are found before branching
+    let clipped = clip_coverage(in.position.xy);
+    let distance = rounded_rect_sdf(in.local, in.half_size, in.radii);
+    let width = max(fwidth(distance), 1e-5);
+
+    // Shadows are cut out under the rectangle casting them, so they
+    // don't show through it when it's translucent
+    var uncovered = 1.0;
+    if all(in.cutout.zw > vec2<f32>(0.0)) {
+        let cutout = rounded_rect_sdf(in.local - in.cutout.xy, in.cutout.zw, in.cutout_radii);
+        uncovered = 1.0 - coverage(cutout, width);
+    }
+
+    let sigma = in.params.w;
+    if sigma > 0.0 {
+        let shadow = shadow_coverage(in.local, in.half_size, in.radii, sigma);
+        let alpha = in.color.a * shadow * uncovered * clipped;
+        if alpha <= 0.0 {
+            discard;
+        }
+        return output_color(vec4<f32>(in.color.rgb, alpha));
+    }
+
+    let border_width = in.params.x;
+    let outline_width = in.params.y;
+    let outline_offset = in.params.z;
+
+    // The fill is inset by the border, so a border wider than half
+    // the rectangle leaves no fill at all
+    let shape = coverage(distance, width);
+    let fill = coverage(distance + border_width, width);
+    let fill_color = brush_color(in.local, in.color, in.brush, in.brush_params);
+    var color = fill_color * fill + premultiply(in.border_color) * (shape - fill);
+
+    // The outline is the band between `outline_offset` and
+    // `outline_offset + outline_width` from the edge
+    if outline_width > 0.0 {
+        let outer = coverage(distance - outline_offset - outline_width, width);
+        let inner = coverage(distance - outline_offset, width);
+        let outline = premultiply(in.outline_color) * clamp(outer - inner, 0.0, 1.0);
+        color = outline + color * (1.0 - outline.a);
+    }
+    color *= uncovered * clipped;
+
+    if color.a <= 0.0 {
+        discard;
+    }
+    return output_color(vec4<f32>(color.rgb / color.a, color.a));
+}
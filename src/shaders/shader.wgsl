@@ -0,0 +1,34 @@
+// The coverage of rasterized glyphs, the bottom right corner is opaque
+// for vertices that aren't text
+@group(2) @binding(0)
+var atlas: texture_2d<f32>;
+@group(2) @binding(1)
+var atlas_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = globals.projection * vec4<f32>(in.position, 0.0, 1.0);
+    out.color = in.color;
+    out.uv = in.uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(atlas, atlas_sampler, in.uv).r;
+    let alpha = in.color.a * coverage * clip_coverage(in.position.xy);
+    return output_color(vec4<f32>(in.color.rgb, alpha));
+}
@@ -0,0 +1,474 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
+
+use crate::{Brush, Color, Position, Rect, Transform};
+
+/// The radius of each corner of a rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct CornerRadii {
+    pub top_left: f32,
+    pub top_right: f32,
+    pub bottom_right: f32,
+    pub bottom_left: f32,
+}
+
+impl CornerRadii {
+    /// Creates new [`CornerRadii`], in clockwise order starting
+    /// from the top left.
+    pub const fn new(top_left: f32, top_right: f32, bottom_right: f32, bottom_left: f32) -> Self {
+        Self {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        }
+    }
+
+    /// The same radius on every corner.
+    pub const fn all(radius: f32) -> Self {
+        Self::new(radius, radius, radius, radius)
+    }
+
+    /// The radii as an array, in clockwise order starting from the top left.
+    pub const fn to_array(self) -> [f32; 4] {
+        [
+            self.top_left,
+            self.top_right,
+            self.bottom_right,
+            self.bottom_left,
+        ]
+    }
+}
+
+/// A border drawn along the inside edge of a rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Border {
+    pub width: f32,
+    pub color: Color,
+}
+
+impl Border {
+    /// Creates a new [`Border`].
+    pub const fn new(width: f32, color: Color) -> Self {
+        Self { width, color }
+    }
+}
+
+/// An outline drawn around a rectangle, usually as a focus ring.
+///
+/// The outline follows the corner radii of the rectangle, and doesn't
+/// affect its size.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Outline {
+    pub width: f32,
+    /// The distance between the edge of the rectangle and the outline.
+    /// Positive offsets place the outline outside the rectangle and
+    /// negative offsets place it inside.
+    pub offset: f32,
+    pub color: Color,
+}
+
+impl Outline {
+    /// Creates a new [`Outline`].
+    pub const fn new(width: f32, offset: f32, color: Color) -> Self {
+        Self {
+            width,
+            offset,
+            color,
+        }
+    }
+
+    /// How far the outline extends outside the rectangle.
+    fn extent(&self) -> f32 {
+        if self.width <= 0.0 {
+            return 0.0;
+        }
+        (self.offset + self.width).max(0.0)
+    }
+}
+
+/// A shadow cast by a rectangle.
+///
+/// The shadow is blurred analytically in the fragment shader, the
+/// blur radius is twice the standard deviation of the gaussian, like
+/// CSS box shadows.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct BoxShadow {
+    pub offset: Position,
+    pub blur: f32,
+    /// How much the shadow grows, or shrinks if negative, before
+    /// it's blurred.
+    pub spread: f32,
+    pub color: Color,
+}
+
+impl BoxShadow {
+    /// Creates a new [`BoxShadow`].
+    pub const fn new(offset: Position, blur: f32, spread: f32, color: Color) -> Self {
+        Self {
+            offset,
+            blur,
+            spread,
+            color,
+        }
+    }
+
+    /// Creates the instance of the shadow of a rectangle with `radii`,
+    /// with the rectangle cut out of it like CSS box shadows.
+    fn instance(&self, rect: Rect, radii: CornerRadii) -> RectInstance {
+        let spread = self.spread;
+        let width = (rect.width() + spread * 2.0).max(0.0);
+        let height = (rect.height() + spread * 2.0).max(0.0);
+        let x = rect.x() + self.offset.x + (rect.width() - width) / 2.0;
+        let y = rect.y() + self.offset.y + (rect.height() - height) / 2.0;
+
+        let shadow_radii = radii.to_array().map(|radius| {
+            if radius > 0.0 {
+                (radius + spread).max(0.0)
+            } else {
+                0.0
+            }
+        });
+
+        // The gaussian is practically zero three standard deviations
+        // away from the edge
+        let sigma = self.blur.max(0.0) / 2.0;
+        RectInstance {
+            rect: [x, y, width, height],
+            color: self.color.to_linear(),
+            radii: shadow_radii,
+            params: [0.0, 0.0, 0.0, sigma],
+            extent: sigma * 3.0,
+            cutout: [
+                -self.offset.x,
+                -self.offset.y,
+                rect.width() / 2.0,
+                rect.height() / 2.0,
+            ],
+            cutout_radii: radii.to_array(),
+            ..Default::default()
+        }
+        .with_transform(Transform::IDENTITY)
+    }
+}
+
+/// A filled rectangle with rounded corners, an optional border, an
+/// optional outline and an optional shadow.
+///
+/// Corners are anti-aliased in the fragment shader using a signed
+/// distance function, radii larger than half the shortest side are
+/// clamped. Rectangles with no radii have sharp corners.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
+pub struct RoundedRect {
+    pub rect: Rect,
+    pub radii: CornerRadii,
+    pub fill: Brush,
+    pub border: Border,
+    pub outline: Outline,
+    pub shadow: Option<BoxShadow>,
+}
+
+impl RoundedRect {
+    /// Creates a new [`RoundedRect`].
+    pub fn new(rect: Rect, radii: CornerRadii, fill: impl Into<Brush>) -> Self {
+        Self {
+            rect,
+            radii,
+            fill: fill.into(),
+            border: Border::new(0.0, Color::TRANSPARENT),
+            outline: Outline::new(0.0, 0.0, Color::TRANSPARENT),
+            shadow: None,
+        }
+    }
+
+    /// Adds a border, a border wider than half the rectangle
+    /// covers the whole rectangle.
+    pub const fn border(mut self, width: f32, color: Color) -> Self {
+        self.border = Border::new(width, color);
+        self
+    }
+
+    /// Adds an outline `offset` away from the edge of the rectangle.
+    pub const fn outline(mut self, width: f32, offset: f32, color: Color) -> Self {
+        self.outline = Outline::new(width, offset, color);
+        self
+    }
+
+    /// Adds a shadow beneath the rectangle.
+    pub const fn shadow(mut self, shadow: BoxShadow) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    /// Creates the instance of the shadow, if there is one.
+    pub fn shadow_instance(&self) -> Option<RectInstance> {
+        self.shadow
+            .map(|shadow| shadow.instance(self.rect, self.radii))
+    }
+
+    /// Creates the instance of the rectangle.
+    ///
+    /// The brush of the instance refers to the gradient stops of the
+    /// fill starting at 0, they need to be offset to where the stops
+    /// are stored.
+    pub fn instance(&self) -> RectInstance {
+        let Rect { position, size } = self.rect;
+        let brush = self.fill.params(size);
+
+        RectInstance {
+            rect: [position.x, position.y, size.width, size.height],
+            color: self.fill.color(),
+            radii: self.radii.to_array(),
+            border_color: self.border.color.to_linear(),
+            outline_color: self.outline.color.to_linear(),
+            params: [
+                self.border.width.max(0.0),
+                self.outline.width.max(0.0),
+                self.outline.offset,
+                0.0,
+            ],
+            brush: [brush.kind, 0, brush.stops, 0],
+            brush_params: brush.params,
+            extent: self.outline.extent(),
+            ..Default::default()
+        }
+        .with_transform(Transform::IDENTITY)
+    }
+}
+
+/// A rectangle drawn with the rounded rect shader.
+///
+/// Rectangles are drawn as instances of a unit quad, which the vertex
+/// shader scales to cover the rectangle along with anything drawn
+/// outside of it, like an outline or a blurred shadow.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Pod, Default, Zeroable)]
+pub struct RectInstance {
+    /// The position and size of the rectangle.
+    pub rect: [f32; 4],
+    pub color: [f32; 4],
+    /// The corner radii, in clockwise order starting from the top left.
+    pub radii: [f32; 4],
+    pub border_color: [f32; 4],
+    pub outline_color: [f32; 4],
+    /// The border width, outline width, outline offset and, for
+    /// shadows, the standard deviation of the blur.
+    pub params: [f32; 4],
+    /// The kind of brush, the index of its first gradient stop
+    /// and the number of stops.
+    pub brush: [u32; 4],
+    /// The direction or center of a gradient.
+    pub brush_params: [f32; 4],
+    /// How far the quad extends past the rectangle on every side.
+    pub extent: f32,
+    /// The linear part of the transform applied to the rectangle,
+    /// `[a, b, c, d]`.
+    pub transform: [f32; 4],
+    /// The translation applied after `transform`.
+    pub translation: [f32; 2],
+    /// The center, relative to the center of the rectangle, and the
+    /// half size of a rounded rectangle that's cut out of this one. It's
+    /// used by shadows so they don't show through the rectangle casting
+    /// them, and is ignored when zero sized.
+    pub cutout: [f32; 4],
+    /// The corner radii of the cutout, in the same order as `radii`.
+    pub cutout_radii: [f32; 4],
+}
+
+impl RectInstance {
+    /// The corners of the unit quad each instance is drawn with, as
+    /// a triangle list.
+    pub const UNIT_QUAD: [[f32; 2]; 6] = [
+        [0.0, 0.0],
+        [1.0, 0.0],
+        [0.0, 1.0],
+        [1.0, 0.0],
+        [0.0, 1.0],
+        [1.0, 1.0],
+    ];
+
+    const UNIT_QUAD_ATTRIBUTES: [VertexAttribute; 1] = [VertexAttribute {
+        format: VertexFormat::Float32x2,
+        offset: 0,
+        shader_location: 0,
+    }];
+
+    const ATTRIBUTES: [VertexAttribute; 13] = [
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(RectInstance, rect) as BufferAddress,
+            shader_location: 1,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(RectInstance, color) as BufferAddress,
+            shader_location: 2,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(RectInstance, radii) as BufferAddress,
+            shader_location: 3,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(RectInstance, border_color) as BufferAddress,
+            shader_location: 4,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(RectInstance, outline_color) as BufferAddress,
+            shader_location: 5,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(RectInstance, params) as BufferAddress,
+            shader_location: 6,
+        },
+        VertexAttribute {
+            format: VertexFormat::Uint32x4,
+            offset: std::mem::offset_of!(RectInstance, brush) as BufferAddress,
+            shader_location: 7,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(RectInstance, brush_params) as BufferAddress,
+            shader_location: 8,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32,
+            offset: std::mem::offset_of!(RectInstance, extent) as BufferAddress,
+            shader_location: 9,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(RectInstance, transform) as BufferAddress,
+            shader_location: 10,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x2,
+            offset: std::mem::offset_of!(RectInstance, translation) as BufferAddress,
+            shader_location: 11,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(RectInstance, cutout) as BufferAddress,
+            shader_location: 12,
+        },
+        VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: std::mem::offset_of!(RectInstance, cutout_radii) as BufferAddress,
+            shader_location: 13,
+        },
+    ];
+
+    /// Returns this instance drawn with `transform`.
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        let [a, b, c, d, e, f] = transform.to_array();
+        self.transform = [a, b, c, d];
+        self.translation = [e, f];
+        self
+    }
+
+    /// Describes how the unit quad buffer is laid out in memory.
+    pub fn unit_quad_layout() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: size_of::<[f32; 2]>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &Self::UNIT_QUAD_ATTRIBUTES,
+        }
+    }
+
+    /// Describes how a buffer of [`RectInstance`]'s is laid out in memory.
+    pub fn layout() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: size_of::<RectInstance>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instance_covers_rect() {
+        let rect = RoundedRect::new(
+            Rect::new(10.0, 20.0, 40.0, 30.0),
+            CornerRadii::all(5.0),
+            Color::WHITE,
+        );
+        let instance = rect.instance();
+
+        assert_eq!(instance.rect, [10.0, 20.0, 40.0, 30.0]);
+        assert_eq!(instance.radii, [5.0; 4]);
+        assert_eq!(instance.extent, 0.0);
+        assert_eq!(instance.transform, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(instance.translation, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn outset_outline_extends_quad() {
+        let rect = RoundedRect::new(
+            Rect::new(10.0, 20.0, 40.0, 30.0),
+            CornerRadii::all(5.0),
+            Color::WHITE,
+        )
+        .outline(2.0, 3.0, Color::BLUE);
+        let instance = rect.instance();
+
+        assert_eq!(instance.rect, [10.0, 20.0, 40.0, 30.0]);
+        assert_eq!(instance.extent, 5.0);
+    }
+
+    #[test]
+    fn shadow_is_offset_and_spread() {
+        let shadow = BoxShadow::new(
+            Position::new(5.0, 10.0),
+            4.0,
+            2.0,
+            Color::BLACK.with_alpha(0.5),
+        );
+        let rect = RoundedRect::new(
+            Rect::new(0.0, 0.0, 40.0, 30.0),
+            CornerRadii::new(5.0, 0.0, 5.0, 0.0),
+            Color::WHITE,
+        )
+        .shadow(shadow);
+        let instance = rect.shadow_instance().unwrap();
+
+        // Spread by 2, and extended by three standard deviations of the blur
+        assert_eq!(instance.rect, [3.0, 8.0, 44.0, 34.0]);
+        assert_eq!(instance.extent, 6.0);
+        assert_eq!(instance.radii, [7.0, 0.0, 7.0, 0.0]);
+        assert_eq!(instance.params, [0.0, 0.0, 0.0, 2.0]);
+        // The rectangle is cut out, relative to the center of the shadow
+        assert_eq!(instance.cutout, [-5.0, -10.0, 20.0, 15.0]);
+        assert_eq!(instance.cutout_radii, [5.0, 0.0, 5.0, 0.0]);
+    }
+
+    #[test]
+    fn no_shadow_instance_without_shadow() {
+        let rect = RoundedRect::new(
+            Rect::new(0.0, 0.0, 40.0, 30.0),
+            CornerRadii::all(5.0),
+            Color::WHITE,
+        );
+        assert!(rect.shadow_instance().is_none());
+    }
+
+    #[test]
+    fn inset_outline_keeps_quad() {
+        let rect = RoundedRect::new(
+            Rect::new(0.0, 0.0, 40.0, 30.0),
+            CornerRadii::all(5.0),
+            Color::WHITE,
+        )
+        .outline(2.0, -4.0, Color::BLUE);
+        let instance = rect.instance();
+
+        assert_eq!(instance.extent, 0.0);
+        assert_eq!(instance.params, [0.0, 2.0, -4.0, 0.0]);
+    }
+}
@@ -0,0 +1,176 @@
+use wgpu::{Device, Surface, SurfaceConfiguration, SurfaceError, SurfaceTexture};
+use winit::dpi::PhysicalSize;
+
+use crate::Error;
+
+/// Something that frames can be acquired from, usually a window
+/// [`Surface`].
+pub(crate) trait FrameSurface {
+    type Frame;
+
+    /// Acquires the next frame to draw into.
+    fn acquire(&mut self) -> Result<Self::Frame, SurfaceError>;
+
+    /// Reconfigures the surface after it was lost or became outdated.
+    fn reconfigure(&mut self);
+}
+
+/// A window surface along with the configuration used to
+/// recreate it.
+pub(crate) struct WindowSurface<'a> {
+    pub surface: &'a Surface<'static>,
+    pub device: &'a Device,
+    pub config: &'a SurfaceConfiguration,
+}
+
+impl FrameSurface for WindowSurface<'_> {
+    type Frame = SurfaceTexture;
+
+    fn acquire(&mut self) -> Result<Self::Frame, SurfaceError> {
+        self.surface.get_current_texture()
+    }
+
+    fn reconfigure(&mut self) {
+        self.surface.configure(self.device, self.config);
+    }
+}
+
+/// The size a surface can be configured with for a window of `size`,
+/// or `None` if the window has no area.
+///
+/// Windows are zero sized when they're minimized, and on some platforms
+/// before they're first shown, which surfaces can't be configured with.
+pub(crate) fn surface_size(size: PhysicalSize<u32>) -> Option<PhysicalSize<u32>> {
+    (size.width > 0 && size.height > 0).then_some(size)
+}
+
+/// Acquires the next frame from a surface, recovering from errors
+/// where possible.
+///
+/// - `Lost` and `Outdated` surfaces are reconfigured and the frame
+///   is retried once.
+/// - `Timeout` and other errors skip the frame, returning `None`.
+/// - `OutOfMemory` is returned as an [`Error::OutOfMemory`].
+pub(crate) fn acquire_frame<S: FrameSurface>(surface: &mut S) -> Result<Option<S::Frame>, Error> {
+    let mut retried = false;
+
+    loop {
+        match surface.acquire() {
+            Ok(frame) => return Ok(Some(frame)),
+            Err(SurfaceError::Lost | SurfaceError::Outdated) if !retried => {
+                log::debug!("Surface lost or outdated, reconfiguring");
+                surface.reconfigure();
+                retried = true;
+            }
+            Err(SurfaceError::OutOfMemory) => return Err(Error::OutOfMemory),
+            Err(SurfaceError::Timeout) => {
+                log::warn!("Timed out acquiring frame, skipping");
+                return Ok(None);
+            }
+            Err(err) => {
+                log::warn!("Failed to acquire frame, skipping: {err}");
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    /// A surface that returns a scripted sequence of results.
+    struct MockSurface {
+        results: VecDeque<Result<(), SurfaceError>>,
+        reconfigured: u32,
+    }
+
+    impl MockSurface {
+        fn new(results: impl IntoIterator<Item = Result<(), SurfaceError>>) -> Self {
+            Self {
+                results: results.into_iter().collect(),
+                reconfigured: 0,
+            }
+        }
+    }
+
+    impl FrameSurface for MockSurface {
+        type Frame = ();
+
+        fn acquire(&mut self) -> Result<Self::Frame, SurfaceError> {
+            self.results.pop_front().expect("Acquired too many frames")
+        }
+
+        fn reconfigure(&mut self) {
+            self.reconfigured += 1;
+        }
+    }
+
+    #[test]
+    fn ignore_zero_sized_windows() {
+        let size = PhysicalSize::new(800, 600);
+        assert_eq!(surface_size(size), Some(size));
+        assert_eq!(surface_size(PhysicalSize::new(0, 600)), None);
+        assert_eq!(surface_size(PhysicalSize::new(800, 0)), None);
+        assert_eq!(surface_size(PhysicalSize::new(0, 0)), None);
+    }
+
+    #[test]
+    fn acquire_frame_without_errors() {
+        let mut surface = MockSurface::new([Ok(())]);
+        let frame = acquire_frame(&mut surface).unwrap();
+        assert_eq!(frame, Some(()));
+        assert_eq!(surface.reconfigured, 0);
+    }
+
+    #[test]
+    fn reconfigure_lost_surface() {
+        let mut surface = MockSurface::new([Err(SurfaceError::Lost), Ok(())]);
+        let frame = acquire_frame(&mut surface).unwrap();
+        assert_eq!(frame, Some(()));
+        assert_eq!(surface.reconfigured, 1);
+    }
+
+    #[test]
+    fn reconfigure_outdated_surface() {
+        let mut surface = MockSurface::new([Err(SurfaceError::Outdated), Ok(())]);
+        let frame = acquire_frame(&mut surface).unwrap();
+        assert_eq!(frame, Some(()));
+        assert_eq!(surface.reconfigured, 1);
+    }
+
+    #[test]
+    fn only_retry_once() {
+        let mut surface =
+            MockSurface::new([Err(SurfaceError::Outdated), Err(SurfaceError::Outdated)]);
+        let frame = acquire_frame(&mut surface).unwrap();
+        assert_eq!(frame, None);
+        assert_eq!(surface.reconfigured, 1);
+    }
+
+    #[test]
+    fn skip_frame_on_timeout() {
+        let mut surface = MockSurface::new([Err(SurfaceError::Timeout)]);
+        let frame = acquire_frame(&mut surface).unwrap();
+        assert_eq!(frame, None);
+        assert_eq!(surface.reconfigured, 0);
+    }
+
+    #[test]
+    fn out_of_memory_error() {
+        let mut surface = MockSurface::new([Err(SurfaceError::OutOfMemory)]);
+        let result = acquire_frame(&mut surface);
+        assert!(matches!(result, Err(Error::OutOfMemory)));
+    }
+
+    #[test]
+    fn out_of_memory_after_reconfigure() {
+        let mut surface =
+            MockSurface::new([Err(SurfaceError::Lost), Err(SurfaceError::OutOfMemory)]);
+        let result = acquire_frame(&mut surface);
+        assert!(matches!(result, Err(Error::OutOfMemory)));
+        assert_eq!(surface.reconfigured, 1);
+    }
+}
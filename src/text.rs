@@ -0,0 +1,483 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rustybuzz::ttf_parser::{self, Tag};
+use rustybuzz::{Face, UnicodeBuffer};
+
+use crate::Size;
+
+/// The tag of the weight axis of variable fonts.
+const WEIGHT_AXIS: Tag = Tag::from_bytes(b"wght");
+
+/// The errors that can occur while loading a [`Font`].
+#[derive(Debug, thiserror::Error)]
+pub enum FontError {
+    /// The data isn't a TrueType or OpenType font.
+    #[error("Failed to parse the font: {0}")]
+    Parse(#[from] ttf_parser::FaceParsingError),
+}
+
+/// How thick the strokes of a font are, from 1 to 1000.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FontWeight(pub u16);
+
+impl FontWeight {
+    pub const THIN: Self = Self(100);
+    pub const EXTRA_LIGHT: Self = Self(200);
+    pub const LIGHT: Self = Self(300);
+    pub const NORMAL: Self = Self(400);
+    pub const MEDIUM: Self = Self(500);
+    pub const SEMI_BOLD: Self = Self(600);
+    pub const BOLD: Self = Self(700);
+    pub const EXTRA_BOLD: Self = Self(800);
+    pub const BLACK: Self = Self(900);
+}
+
+impl Default for FontWeight {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+/// A TrueType or OpenType font.
+///
+/// Fonts are cheap to clone, the font data is shared.
+#[derive(Clone)]
+pub struct Font {
+    data: Arc<FontData>,
+}
+
+struct FontData {
+    /// Identifies the font in the glyph atlas.
+    id: u64,
+    bytes: Vec<u8>,
+    index: u32,
+    weight: FontWeight,
+    /// The range of the weight axis of variable fonts.
+    weight_axis: Option<(f32, f32)>,
+}
+
+impl Font {
+    /// Loads a font from the bytes of a TTF or OTF file, such as a
+    /// file included with [`include_bytes`].
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Result<Self, FontError> {
+        Self::from_collection(bytes, 0)
+    }
+
+    /// Loads the font at `index` in a font collection, such as a
+    /// TTC file.
+    pub fn from_collection(bytes: impl Into<Vec<u8>>, index: u32) -> Result<Self, FontError> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        let bytes = bytes.into();
+        let face = ttf_parser::Face::parse(&bytes, index)?;
+        let weight = FontWeight(face.weight().to_number());
+        let weight_axis = face
+            .variation_axes()
+            .into_iter()
+            .find(|axis| axis.tag == WEIGHT_AXIS)
+            .map(|axis| (axis.min_value, axis.max_value));
+
+        Ok(Self {
+            data: Arc::new(FontData {
+                id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+                bytes,
+                index,
+                weight,
+                weight_axis,
+            }),
+        })
+    }
+
+    /// The weight of the font, or its default weight for
+    /// variable fonts.
+    pub fn weight(&self) -> FontWeight {
+        self.data.weight
+    }
+
+    /// Returns `true` if the font can be drawn at any weight in a
+    /// range, rather than only its own weight.
+    pub fn is_variable(&self) -> bool {
+        self.data.weight_axis.is_some()
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.data.id
+    }
+
+    /// How far `weight` is from the weights this font supports.
+    fn weight_distance(&self, weight: FontWeight) -> u16 {
+        match self.data.weight_axis {
+            Some((min, max)) => {
+                let weight = weight.0 as f32;
+                (weight - weight.clamp(min, max)).abs() as u16
+            }
+            None => self.weight().0.abs_diff(weight.0),
+        }
+    }
+
+    /// The weight this font is drawn at when `weight` is requested,
+    /// which only varies for variable fonts.
+    pub(crate) fn resolve_weight(&self, weight: FontWeight) -> FontWeight {
+        match self.data.weight_axis {
+            Some((min, max)) => FontWeight((weight.0 as f32).clamp(min, max) as u16),
+            None => self.weight(),
+        }
+    }
+
+    /// Parses the font, set to `weight` if it's variable.
+    ///
+    /// The face borrows the shared font data, so it's parsed again for
+    /// each use rather than stored.
+    pub(crate) fn face(&self, weight: FontWeight) -> Face<'_> {
+        // The data was parsed when the font was created
+        let face = ttf_parser::Face::parse(&self.data.bytes, self.data.index)
+            .expect("font data was already parsed");
+        let mut face = Face::from_face(face);
+        if self.is_variable() {
+            face.set_variation(WEIGHT_AXIS, weight.0 as f32);
+        }
+        face
+    }
+
+    /// The vertical metrics of a line of text in this font.
+    pub fn line_metrics(&self, style: &TextStyle) -> LineMetrics {
+        let face = self.face(self.resolve_weight(style.weight));
+        let scale = style.size / face.units_per_em() as f32;
+        let ascent = face.ascender() as f32 * scale;
+        let descent = -face.descender() as f32 * scale;
+        let line_height = match style.line_height {
+            Some(line_height) => line_height * style.size,
+            None => ascent + descent + face.line_gap() as f32 * scale,
+        };
+
+        LineMetrics {
+            ascent,
+            descent,
+            line_height,
+        }
+    }
+}
+
+impl fmt::Debug for Font {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Font")
+            .field("id", &self.data.id)
+            .field("weight", &self.data.weight)
+            .field("variable", &self.is_variable())
+            .finish()
+    }
+}
+
+impl PartialEq for Font {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+/// The size, weight and spacing of text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    /// The font size in logical pixels.
+    pub size: f32,
+    pub weight: FontWeight,
+    /// The height of each line as a multiple of the font size, or
+    /// `None` to use the spacing the font was designed with.
+    pub line_height: Option<f32>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new(16.0)
+    }
+}
+
+impl TextStyle {
+    /// Creates a [`TextStyle`] with a font size of `size` logical pixels.
+    pub const fn new(size: f32) -> Self {
+        Self {
+            size,
+            weight: FontWeight::NORMAL,
+            line_height: None,
+        }
+    }
+
+    /// Sets the font weight.
+    pub const fn weight(mut self, weight: FontWeight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Sets the height of each line as a multiple of the font size.
+    pub const fn line_height(mut self, line_height: f32) -> Self {
+        self.line_height = Some(line_height);
+        self
+    }
+}
+
+/// The vertical metrics of a line of text, in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LineMetrics {
+    /// How far the font extends above the baseline.
+    pub ascent: f32,
+    /// How far the font extends below the baseline.
+    pub descent: f32,
+    pub line_height: f32,
+}
+
+impl LineMetrics {
+    /// The distance from the top of the line to the baseline, with
+    /// any extra line height split evenly above and below the text.
+    pub fn baseline(&self) -> f32 {
+        (self.line_height - self.ascent - self.descent) / 2.0 + self.ascent
+    }
+}
+
+/// A glyph positioned by shaping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    /// The glyph in the font, which isn't related to any character
+    /// after ligatures and other substitutions.
+    pub id: u16,
+    /// The byte index of the first character in the text this glyph
+    /// was shaped from.
+    pub cluster: usize,
+    /// The position of the glyph's origin relative to the start of the
+    /// baseline, in logical pixels.
+    pub x: f32,
+    pub y: f32,
+    /// How far the glyph moves the pen.
+    pub advance: f32,
+}
+
+/// A single line of text shaped with a [`Font`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapedText {
+    font: Font,
+    style: TextStyle,
+    glyphs: Vec<ShapedGlyph>,
+    width: f32,
+    metrics: LineMetrics,
+}
+
+impl ShapedText {
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    pub fn style(&self) -> &TextStyle {
+        &self.style
+    }
+
+    /// The glyphs in visual order, left to right, which is the reverse
+    /// of the text for right to left scripts.
+    pub fn glyphs(&self) -> &[ShapedGlyph] {
+        &self.glyphs
+    }
+
+    /// The distance from the start of the first glyph to the end of
+    /// the last, in logical pixels.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn metrics(&self) -> LineMetrics {
+        self.metrics
+    }
+
+    /// The width and line height of the text.
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.metrics.line_height)
+    }
+}
+
+/// The fonts that text can be drawn with.
+///
+/// Each piece of text is shaped with the font closest to the requested
+/// weight. Fonts are cheap to clone. Widgets measure their text with
+/// the fonts of the window, from [`LayoutContext::fonts`].
+///
+/// [`LayoutContext::fonts`]: crate::LayoutContext::fonts
+#[derive(Debug, Clone)]
+pub struct Fonts {
+    fonts: Vec<Font>,
+}
+
+#[cfg(feature = "bundled-font")]
+impl Default for Fonts {
+    /// Creates a collection containing DejaVu Sans, which is bundled
+    /// with the crate by the default `bundled-font` feature so that text
+    /// can be drawn without loading a font.
+    fn default() -> Self {
+        let bytes = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+        Self::new(Font::from_bytes(bytes.as_slice()).expect("the bundled font is valid"))
+    }
+}
+
+impl Fonts {
+    /// Creates a collection containing `font`.
+    pub fn new(font: Font) -> Self {
+        Self { fonts: vec![font] }
+    }
+
+    /// Adds a font, usually another weight of the same typeface.
+    pub fn add(&mut self, font: Font) {
+        self.fonts.push(font);
+    }
+
+    pub fn fonts(&self) -> &[Font] {
+        &self.fonts
+    }
+
+    /// The font closest to `weight`, preferring the first font that
+    /// was added when fonts are equally close.
+    pub fn select(&self, weight: FontWeight) -> &Font {
+        self.fonts
+            .iter()
+            .min_by_key(|font| font.weight_distance(weight))
+            .expect("a collection always has a font")
+    }
+
+    /// Shapes `text` into positioned glyphs, applying kerning,
+    /// ligatures and the shaping rules of its script.
+    ///
+    /// The script and direction are detected from the text. Line
+    /// breaks aren't handled, the text is shaped as a single line.
+    pub fn shape(&self, text: &str, style: &TextStyle) -> ShapedText {
+        let font = self.select(style.weight);
+        let face = font.face(font.resolve_weight(style.weight));
+        let scale = style.size / face.units_per_em() as f32;
+
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        let output = rustybuzz::shape(&face, &[], buffer);
+
+        let mut pen = 0.0;
+        let glyphs = output
+            .glyph_infos()
+            .iter()
+            .zip(output.glyph_positions())
+            .map(|(info, position)| {
+                let glyph = ShapedGlyph {
+                    id: info.glyph_id as u16,
+                    cluster: info.cluster as usize,
+                    x: pen + position.x_offset as f32 * scale,
+                    y: -position.y_offset as f32 * scale,
+                    advance: position.x_advance as f32 * scale,
+                };
+                pen += glyph.advance;
+                glyph
+            })
+            .collect();
+
+        ShapedText {
+            font: font.clone(),
+            style: *style,
+            glyphs,
+            width: pen,
+            metrics: font.line_metrics(style),
+        }
+    }
+
+    /// The size of `text` when drawn as a single line, for widgets to
+    /// size themselves during layout.
+    pub fn measure(&self, text: &str, style: &TextStyle) -> Size {
+        self.shape(text, style).size()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::LazyLock;
+
+    use super::*;
+
+    /// DejaVu Sans, which is parsed once and shared by every test.
+    pub(crate) fn fonts() -> Fonts {
+        static FONTS: LazyLock<Fonts> = LazyLock::new(|| {
+            let bytes = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+            Fonts::new(Font::from_bytes(bytes.as_slice()).unwrap())
+        });
+        FONTS.clone()
+    }
+
+    #[test]
+    fn reject_invalid_fonts() {
+        assert!(matches!(
+            Font::from_bytes(b"not a font".as_slice()),
+            Err(FontError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn measure_scales_with_size() {
+        let fonts = fonts();
+        let small = fonts.measure("Hello", &TextStyle::new(10.0));
+        let large = fonts.measure("Hello", &TextStyle::new(20.0));
+
+        assert!(small.width > 0.0);
+        assert!((large.width - small.width * 2.0).abs() < 1e-3);
+        assert!((large.height - small.height * 2.0).abs() < 1e-3);
+        assert_eq!(fonts.measure("", &TextStyle::new(10.0)).width, 0.0);
+    }
+
+    #[test]
+    fn line_height() {
+        let fonts = fonts();
+        let style = TextStyle::new(20.0).line_height(1.5);
+        let text = fonts.shape("Hello", &style);
+
+        assert_eq!(text.size().height, 30.0);
+        let metrics = text.metrics();
+        assert!(metrics.baseline() > metrics.ascent);
+        assert!(metrics.baseline() < 30.0 - metrics.descent);
+    }
+
+    #[test]
+    fn apply_kerning() {
+        let fonts = fonts();
+        let style = TextStyle::new(32.0);
+        let pair = fonts.measure("AV", &style).width;
+        let apart = fonts.measure("A", &style).width + fonts.measure("V", &style).width;
+
+        assert!(pair < apart, "{pair} should be kerned tighter than {apart}");
+    }
+
+    #[test]
+    fn apply_ligatures() {
+        let text = fonts().shape("ffi", &TextStyle::default());
+
+        assert!(text.glyphs().len() < 3);
+        assert_eq!(text.glyphs()[0].cluster, 0);
+    }
+
+    #[test]
+    fn shape_right_to_left() {
+        // "Peace" in Arabic, which joins its letters and runs right to left
+        let text = fonts().shape("سلام", &TextStyle::default());
+        let clusters: Vec<_> = text.glyphs().iter().map(|glyph| glyph.cluster).collect();
+
+        assert_eq!(clusters.first(), Some(&6));
+        assert_eq!(clusters.last(), Some(&0));
+        assert!(text.glyphs().iter().all(|glyph| glyph.id != 0));
+    }
+
+    #[test]
+    fn share_font_data_between_clones() {
+        let font = fonts().fonts()[0].clone();
+        let clone = font.clone();
+        assert!(Arc::ptr_eq(&font.data, &clone.data));
+        assert_eq!(clone.face(FontWeight::BOLD).units_per_em(), 2048);
+    }
+
+    #[test]
+    fn select_closest_weight() {
+        let fonts = fonts();
+        assert_eq!(fonts.select(FontWeight::BOLD).weight(), FontWeight::NORMAL);
+
+        let font = fonts.fonts()[0].clone();
+        assert_eq!(font.resolve_weight(FontWeight::BOLD), FontWeight::NORMAL);
+        assert_eq!(font.weight_distance(FontWeight::BOLD), 300);
+    }
+}
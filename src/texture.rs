@@ -0,0 +1,644 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+use image::RgbaImage;
+use image::imageops::{self, FilterType};
+use wgpu::{
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Device,
+    Extent3d, FilterMode, Origin3d, Queue, Sampler, SamplerBindingType, SamplerDescriptor,
+    ShaderStages, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture, TextureAspect,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+    TextureView, TextureViewDimension,
+};
+
+use crate::atlas::{Allocation, AtlasAllocator};
+use crate::draw::{Batch, BatchKind, DrawList, PagedQuad};
+use crate::{Constraints, DrawContext, LayoutContext, Rect, Size, Widget};
+
+/// The errors that can occur while loading an [`ImageData`].
+#[derive(Debug, thiserror::Error)]
+pub enum ImageError {
+    /// The file couldn't be read.
+    #[error("Failed to read the image: {0}")]
+    Io(#[from] std::io::Error),
+    /// The data isn't in a supported image format.
+    #[error("Failed to decode the image: {0}")]
+    Decode(#[from] image::ImageError),
+}
+
+/// Decoded RGBA pixels, which are uploaded to the GPU the first time
+/// they're drawn.
+///
+/// Images are identified by a hash of their pixels, so loading the same
+/// image twice shares one texture. Cloning is cheap.
+#[derive(Debug, Clone)]
+pub struct ImageData {
+    id: u64,
+    pixels: Arc<RgbaImage>,
+}
+
+impl ImageData {
+    /// Decodes an image in any format supported by the `image` crate,
+    /// such as PNG or JPEG, guessing the format from its contents.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ImageError> {
+        Ok(Self::from_rgba(
+            image::load_from_memory(bytes)?.into_rgba8(),
+        ))
+    }
+
+    /// Reads and decodes the image at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ImageError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Wraps pixels that were already decoded.
+    pub fn from_rgba(pixels: RgbaImage) -> Self {
+        let mut hasher = DefaultHasher::new();
+        pixels.dimensions().hash(&mut hasher);
+        pixels.as_raw().hash(&mut hasher);
+
+        Self {
+            id: hasher.finish(),
+            pixels: Arc::new(pixels),
+        }
+    }
+
+    /// The hash of the pixels, which is equal for images with the
+    /// same content.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.pixels.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.pixels.height()
+    }
+
+    /// The size of the image, with one pixel per logical pixel.
+    pub fn size(&self) -> Size {
+        Size::new(self.width() as f32, self.height() as f32)
+    }
+
+    pub fn pixels(&self) -> &RgbaImage {
+        &self.pixels
+    }
+}
+
+impl PartialEq for ImageData {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+/// How an image is sized to the rectangle it's drawn in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFit {
+    /// Scales the image to fit inside the rectangle, keeping its aspect
+    /// ratio, and centers it.
+    #[default]
+    Contain,
+    /// Scales the image to cover the rectangle, keeping its aspect
+    /// ratio, and crops the edges that don't fit.
+    Cover,
+    /// Stretches the image to the rectangle.
+    Fill,
+    /// Draws the image at its own size centered in the rectangle,
+    /// cropping the edges that don't fit.
+    None,
+}
+
+impl ImageFit {
+    /// Places an image of `size` in `rect`, returning the area it's drawn
+    /// to and the texture coordinates of that area as `[u0, v0, u1, v1]`.
+    ///
+    /// Images are cropped by their texture coordinates rather than
+    /// clipped. Returns `None` if nothing of the image is visible.
+    pub fn place(self, size: Size, rect: Rect) -> Option<(Rect, [f32; 4])> {
+        if size.width <= 0.0 || size.height <= 0.0 || rect.is_empty() {
+            return None;
+        }
+
+        let scale = match self {
+            Self::Contain => (rect.width() / size.width).min(rect.height() / size.height),
+            Self::Cover => (rect.width() / size.width).max(rect.height() / size.height),
+            Self::Fill => return Some((rect, [0.0, 0.0, 1.0, 1.0])),
+            Self::None => 1.0,
+        };
+        let (width, height) = (size.width * scale, size.height * scale);
+        let image = Rect::new(
+            rect.x() + (rect.width() - width) / 2.0,
+            rect.y() + (rect.height() - height) / 2.0,
+            width,
+            height,
+        );
+
+        let visible = image.intersection(rect);
+        if visible.is_empty() {
+            return None;
+        }
+        let uv = [
+            (visible.x() - image.x()) / width,
+            (visible.y() - image.y()) / height,
+            (visible.right() - image.x()) / width,
+            (visible.bottom() - image.y()) / height,
+        ];
+        Some((visible, uv))
+    }
+}
+
+/// How the pixels of an image are sampled when it's scaled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ImageSampling {
+    /// Blends neighbouring pixels, for photos and smooth images.
+    #[default]
+    Linear,
+    /// Uses the closest pixel, for pixel art.
+    Nearest,
+}
+
+/// Identifies the texture and sampler that an image is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct TextureKey {
+    pub image: u64,
+    pub sampling: ImageSampling,
+}
+
+/// A widget that draws an image.
+///
+/// Images are sized to their own size in logical pixels, scaled down to
+/// fit the constraints while keeping their aspect ratio.
+#[derive(Debug, Clone)]
+pub struct Image {
+    data: ImageData,
+    fit: ImageFit,
+    sampling: ImageSampling,
+}
+
+impl Image {
+    /// Creates an [`Image`] that's contained in its bounds and
+    /// sampled linearly.
+    pub fn new(data: ImageData) -> Self {
+        Self {
+            data,
+            fit: ImageFit::default(),
+            sampling: ImageSampling::default(),
+        }
+    }
+
+    pub fn fit(mut self, fit: ImageFit) -> Self {
+        self.fit = fit;
+        self
+    }
+
+    pub fn sampling(mut self, sampling: ImageSampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn data(&self) -> &ImageData {
+        &self.data
+    }
+}
+
+impl Widget for Image {
+    fn layout(&mut self, constraints: Constraints, _: &mut LayoutContext) -> Size {
+        let size = self.data.size();
+        let max = constraints.max;
+        let scale = (max.width / size.width)
+            .min(max.height / size.height)
+            .min(1.0);
+        if scale.is_finite() {
+            Size::new(size.width * scale, size.height * scale)
+        } else {
+            size
+        }
+    }
+
+    fn draw(&self, ctx: &mut DrawContext) {
+        let bounds = ctx.bounds();
+        ctx.image(&self.data, bounds, self.fit, self.sampling);
+    }
+}
+
+/// A texture with a bind group for each sampling mode it's drawn with.
+#[derive(Debug)]
+struct SampledTexture {
+    texture: Texture,
+    view: TextureView,
+    bind_groups: HashMap<ImageSampling, BindGroup>,
+}
+
+impl SampledTexture {
+    fn new(texture: Texture) -> Self {
+        Self {
+            view: texture.create_view(&Default::default()),
+            texture,
+            bind_groups: HashMap::new(),
+        }
+    }
+
+    /// Creates the bind group for `sampling` if it doesn't exist yet.
+    fn prepare(
+        &mut self,
+        device: &Device,
+        layout: &BindGroupLayout,
+        samplers: &Samplers,
+        sampling: ImageSampling,
+    ) {
+        let view = &self.view;
+        self.bind_groups.entry(sampling).or_insert_with(|| {
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Image bind group"),
+                layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(view),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(samplers.get(sampling)),
+                    },
+                ],
+            })
+        });
+    }
+}
+
+#[derive(Debug)]
+struct Samplers {
+    linear: Sampler,
+    nearest: Sampler,
+}
+
+impl Samplers {
+    fn get(&self, sampling: ImageSampling) -> &Sampler {
+        match sampling {
+            ImageSampling::Linear => &self.linear,
+            ImageSampling::Nearest => &self.nearest,
+        }
+    }
+}
+
+/// A texture for an image too large for the atlas.
+#[derive(Debug)]
+struct CachedTexture {
+    texture: SampledTexture,
+    /// The frame the texture was last drawn in.
+    last_used: u64,
+}
+
+/// The textures of the images drawn in recent frames.
+///
+/// Small images are packed into shared atlas pages, so that drawing
+/// many icons doesn't need a draw call for each of them. Larger images
+/// get a texture of their own.
+#[derive(Debug)]
+pub(crate) struct TextureCache {
+    layout: BindGroupLayout,
+    samplers: Samplers,
+    textures: HashMap<u64, CachedTexture>,
+    pages: Vec<SampledTexture>,
+    /// Where each image is in the atlas, by [`ImageData::id`], with a
+    /// border around it.
+    atlas: AtlasAllocator<u64>,
+    frame: u64,
+}
+
+impl TextureCache {
+    /// The number of frames a texture is kept for after it was last drawn.
+    const MAX_UNUSED_FRAMES: u64 = 120;
+
+    /// The width and height of each atlas page.
+    const PAGE_SIZE: u32 = 1024;
+
+    /// The most atlas pages before images are evicted.
+    const MAX_PAGES: usize = 4;
+
+    /// The largest width and height of images packed into the atlas.
+    const MAX_ATLAS_IMAGE: u32 = 256;
+
+    pub fn new(device: &Device) -> Self {
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Image layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let sampler = |label, filter| {
+            device.create_sampler(&SamplerDescriptor {
+                label: Some(label),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                mag_filter: filter,
+                min_filter: filter,
+                ..Default::default()
+            })
+        };
+
+        let size = [Self::PAGE_SIZE; 2];
+        Self {
+            layout,
+            samplers: Samplers {
+                linear: sampler("Linear image sampler", FilterMode::Linear),
+                nearest: sampler("Nearest image sampler", FilterMode::Nearest),
+            },
+            textures: HashMap::new(),
+            pages: Vec::new(),
+            atlas: AtlasAllocator::new(size, 0, Self::MAX_PAGES),
+            frame: 0,
+        }
+    }
+
+    pub fn layout(&self) -> &BindGroupLayout {
+        &self.layout
+    }
+
+    /// Uploads the images in `list` that aren't cached yet, and drops
+    /// textures that haven't been drawn for a while.
+    ///
+    /// Quads of images in the atlas have their texture coordinates
+    /// moved into their page, and are returned with it.
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        list: &mut DrawList,
+    ) -> Vec<PagedQuad> {
+        self.frame += 1;
+        self.atlas.next_frame();
+
+        let mut packed = HashMap::new();
+        for (key, image) in &list.images {
+            let allocation = match packed.get(&key.image) {
+                Some(allocation) => Some(*allocation),
+                None => self.pack(device, queue, image),
+            };
+            if let Some(allocation) = allocation {
+                packed.insert(key.image, allocation);
+                self.pages[allocation.page].prepare(
+                    device,
+                    &self.layout,
+                    &self.samplers,
+                    key.sampling,
+                );
+                continue;
+            }
+
+            let texture = self
+                .textures
+                .entry(key.image)
+                .or_insert_with(|| CachedTexture {
+                    texture: SampledTexture::new(upload(device, queue, image.pixels())),
+                    last_used: 0,
+                });
+            texture.last_used = self.frame;
+            texture
+                .texture
+                .prepare(device, &self.layout, &self.samplers, key.sampling);
+        }
+
+        let frame = self.frame;
+        self.textures
+            .retain(|_, texture| frame - texture.last_used <= Self::MAX_UNUSED_FRAMES);
+
+        let mut quads = Vec::new();
+        for quad in &list.image_quads {
+            let Some(Allocation { page, rect }) = packed.get(&quad.key.image) else {
+                continue;
+            };
+
+            // Skip the border around the image
+            let [x, y, width, height] = rect.map(|value| value as f32);
+            let (x, y, width, height) = (x + 1.0, y + 1.0, width - 2.0, height - 2.0);
+            let first = quad.first_vertex as usize;
+            for vertex in &mut list.mesh.vertices_mut()[first..first + 4] {
+                let [u, v] = vertex.uv;
+                vertex.uv = [
+                    (x + u * width) / Self::PAGE_SIZE as f32,
+                    (y + v * height) / Self::PAGE_SIZE as f32,
+                ];
+            }
+            quads.push(PagedQuad {
+                first_index: quad.first_index,
+                kind: BatchKind::AtlasImage(quad.key.sampling),
+                page: *page as u32,
+            });
+        }
+        quads
+    }
+
+    /// Puts a small `image` in the atlas if it isn't there yet,
+    /// returning where it is including its border.
+    fn pack(&mut self, device: &Device, queue: &Queue, image: &ImageData) -> Option<Allocation> {
+        if image.width() > Self::MAX_ATLAS_IMAGE || image.height() > Self::MAX_ATLAS_IMAGE {
+            return None;
+        }
+        if let Some(allocation) = self.atlas.get(&image.id()) {
+            return Some(allocation);
+        }
+
+        // The edges are repeated into a border, so linear sampling
+        // doesn't blend in the neighbouring images
+        let pixels = image.pixels();
+        let (width, height) = pixels.dimensions();
+        let bordered = RgbaImage::from_fn(width + 2, height + 2, |x, y| {
+            *pixels.get_pixel(
+                x.saturating_sub(1).min(width - 1),
+                y.saturating_sub(1).min(height - 1),
+            )
+        });
+        let allocation = self.atlas.insert(image.id(), width + 2, height + 2)?;
+
+        while self.pages.len() < self.atlas.pages() {
+            let texture = create_texture(device, "Image atlas", Self::PAGE_SIZE, Self::PAGE_SIZE);
+            self.pages.push(SampledTexture::new(texture));
+        }
+        let [x, y, ..] = allocation.rect;
+        write_texture(
+            queue,
+            &self.pages[allocation.page].texture,
+            Origin3d { x, y, z: 0 },
+            &bordered,
+        );
+        Some(allocation)
+    }
+
+    /// The bind group to draw an image batch with, after
+    /// [`TextureCache::prepare`].
+    pub fn bind_group(&self, batch: &Batch) -> Option<&BindGroup> {
+        let (texture, sampling) = match batch.kind {
+            BatchKind::Image(key) => (&self.textures.get(&key.image)?.texture, key.sampling),
+            BatchKind::AtlasImage(sampling) => {
+                (self.pages.get(batch.page.unwrap_or(0) as usize)?, sampling)
+            }
+            BatchKind::Mesh | BatchKind::Rect => return None,
+        };
+        texture.bind_groups.get(&sampling)
+    }
+}
+
+fn create_texture(device: &Device, label: &str, width: u32, height: u32) -> Texture {
+    device.create_texture(&TextureDescriptor {
+        label: Some(label),
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Rgba8UnormSrgb,
+        usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+        view_formats: &[],
+    })
+}
+
+fn write_texture(queue: &Queue, texture: &Texture, origin: Origin3d, pixels: &RgbaImage) {
+    let (width, height) = pixels.dimensions();
+    queue.write_texture(
+        TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin,
+            aspect: TextureAspect::All,
+        },
+        pixels.as_raw(),
+        TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(width * 4),
+            rows_per_image: Some(height),
+        },
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+/// Creates a texture for `pixels`, scaling them down if they're larger
+/// than the device supports.
+fn upload(device: &Device, queue: &Queue, pixels: &RgbaImage) -> Texture {
+    let max = device.limits().max_texture_dimension_2d;
+    let scaled;
+    let mut pixels = pixels;
+    if pixels.width() > max || pixels.height() > max {
+        log::warn!(
+            "a {}x{} image is larger than the maximum texture size of {max}, it will be scaled down",
+            pixels.width(),
+            pixels.height(),
+        );
+        let scale = max as f32 / pixels.width().max(pixels.height()) as f32;
+        let width = ((pixels.width() as f32 * scale) as u32).clamp(1, max);
+        let height = ((pixels.height() as f32 * scale) as u32).clamp(1, max);
+        scaled = imageops::resize(pixels, width, height, FilterType::Triangle);
+        pixels = &scaled;
+    }
+
+    let texture = create_texture(device, "Image texture", pixels.width(), pixels.height());
+    write_texture(queue, &texture, Origin3d::ZERO, pixels);
+    texture
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+
+    fn checkerboard() -> ImageData {
+        ImageData::from_rgba(RgbaImage::from_fn(4, 2, |x, y| {
+            let value = if (x + y) % 2 == 0 { 255 } else { 0 };
+            image::Rgba([value, value, value, 255])
+        }))
+    }
+
+    #[test]
+    fn decode_and_hash() {
+        let mut png = Vec::new();
+        checkerboard()
+            .pixels()
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let decoded = ImageData::from_bytes(&png).unwrap();
+        assert_eq!(decoded.size(), Size::new(4.0, 2.0));
+        assert_eq!(decoded.id(), checkerboard().id());
+
+        let other = ImageData::from_rgba(RgbaImage::new(4, 2));
+        assert_ne!(other.id(), decoded.id());
+        assert!(matches!(
+            ImageData::from_bytes(b"not an image"),
+            Err(ImageError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn fit_modes() {
+        let size = Size::new(200.0, 100.0);
+        let rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+
+        let (contain, uv) = ImageFit::Contain.place(size, rect).unwrap();
+        assert_eq!(contain, Rect::new(0.0, 25.0, 100.0, 50.0));
+        assert_eq!(uv, [0.0, 0.0, 1.0, 1.0]);
+
+        let (cover, uv) = ImageFit::Cover.place(size, rect).unwrap();
+        assert_eq!(cover, rect);
+        assert_eq!(uv, [0.25, 0.0, 0.75, 1.0]);
+
+        let (fill, uv) = ImageFit::Fill.place(size, rect).unwrap();
+        assert_eq!(fill, rect);
+        assert_eq!(uv, [0.0, 0.0, 1.0, 1.0]);
+
+        let (none, uv) = ImageFit::None
+            .place(size, Rect::new(0.0, 0.0, 100.0, 200.0))
+            .unwrap();
+        assert_eq!(none, Rect::new(0.0, 50.0, 100.0, 100.0));
+        assert_eq!(uv, [0.25, 0.0, 0.75, 1.0]);
+
+        assert_eq!(ImageFit::Contain.place(size, Rect::default()), None);
+    }
+
+    #[test]
+    fn layout_keeps_aspect_ratio() {
+        let fonts = crate::text::tests::fonts();
+        let mut node = crate::WidgetNode::new(Image::new(checkerboard()));
+        assert_eq!(
+            node.layout(Constraints::unbounded(), &fonts),
+            Size::new(4.0, 2.0)
+        );
+        assert_eq!(
+            node.layout(Constraints::loose(Size::new(2.0, 10.0)), &fonts),
+            Size::new(2.0, 1.0)
+        );
+
+        let mut ctx = DrawContext::new();
+        node.draw(Position::ZERO, &mut ctx);
+        let vertices = ctx.vertices();
+        assert_eq!(vertices[3].position, [2.0, 1.0]);
+        assert_eq!(vertices[3].uv, [1.0, 1.0]);
+    }
+}
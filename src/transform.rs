@@ -0,0 +1,264 @@
+use crate::{Position, Rect};
+
+/// A 2D affine transform.
+///
+/// A point `(x, y)` is transformed to
+/// `(a * x + c * y + e, b * x + d * y + f)`, where the coefficients are
+/// `[a, b, c, d, e, f]`. Since the y axis points down, positive angles
+/// rotate clockwise.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Transform {
+    matrix: [f32; 6],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self::new([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    /// Creates a [`Transform`] from its coefficients, `[a, b, c, d, e, f]`.
+    pub const fn new(matrix: [f32; 6]) -> Self {
+        Self { matrix }
+    }
+
+    /// A transform that moves points by `x` and `y`.
+    pub const fn translate(x: f32, y: f32) -> Self {
+        Self::new([1.0, 0.0, 0.0, 1.0, x, y])
+    }
+
+    /// A transform that scales points away from the origin.
+    pub const fn scale(x: f32, y: f32) -> Self {
+        Self::new([x, 0.0, 0.0, y, 0.0, 0.0])
+    }
+
+    /// A transform that rotates points around the origin by `angle`
+    /// radians.
+    pub fn rotate(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    /// A transform that skews points by `x` radians along the x axis
+    /// and `y` radians along the y axis.
+    pub fn skew(x: f32, y: f32) -> Self {
+        Self::new([1.0, y.tan(), x.tan(), 1.0, 0.0, 0.0])
+    }
+
+    /// The coefficients, `[a, b, c, d, e, f]`.
+    pub const fn to_array(self) -> [f32; 6] {
+        self.matrix
+    }
+
+    /// Returns the transform that applies `self` and then `other`.
+    pub fn then(self, other: Self) -> Self {
+        let [a, b, c, d, e, f] = self.matrix;
+        let [oa, ob, oc, od, oe, of] = other.matrix;
+        Self::new([
+            oa * a + oc * b,
+            ob * a + od * b,
+            oa * c + oc * d,
+            ob * c + od * d,
+            oa * e + oc * f + oe,
+            ob * e + od * f + of,
+        ])
+    }
+
+    /// Returns this transform applied around `point` rather than
+    /// the origin, for example to rotate around the center of a shape.
+    pub fn around(self, point: Position) -> Self {
+        Self::translate(-point.x, -point.y)
+            .then(self)
+            .then(Self::translate(point.x, point.y))
+    }
+
+    /// Returns the transform that undoes this one, or `None` if this
+    /// transform collapses points onto a line.
+    ///
+    /// Transforms that scale down by a large factor can still be
+    /// inverted, as long as the inverse fits in an `f32`.
+    pub fn invert(self) -> Option<Self> {
+        let [a, b, c, d, e, f] = self.matrix;
+        let determinant = a * d - b * c;
+        if determinant == 0.0 {
+            return None;
+        }
+
+        let (a, b, c, d) = (
+            d / determinant,
+            -b / determinant,
+            -c / determinant,
+            a / determinant,
+        );
+        let inverse = [a, b, c, d, -(a * e + c * f), -(b * e + d * f)];
+        inverse
+            .iter()
+            .all(|value| value.is_finite())
+            .then(|| Self::new(inverse))
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    /// Whether this transform keeps edges parallel to the axes, which
+    /// is true for translations and scales.
+    pub fn is_axis_aligned(&self) -> bool {
+        let [_, b, c, ..] = self.matrix;
+        b == 0.0 && c == 0.0
+    }
+
+    pub fn transform_point(&self, point: Position) -> Position {
+        let [a, b, c, d, e, f] = self.matrix;
+        Position::new(a * point.x + c * point.y + e, b * point.x + d * point.y + f)
+    }
+
+    /// The smallest axis-aligned rectangle containing `rect` once
+    /// it's transformed.
+    pub fn transform_rect(&self, rect: Rect) -> Rect {
+        let corners = [
+            Position::new(rect.x(), rect.y()),
+            Position::new(rect.right(), rect.y()),
+            Position::new(rect.x(), rect.bottom()),
+            Position::new(rect.right(), rect.bottom()),
+        ]
+        .map(|corner| self.transform_point(corner));
+
+        let min_x = corners.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+        let min_y = corners.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let max_x = corners
+            .iter()
+            .map(|p| p.x)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let max_y = corners
+            .iter()
+            .map(|p| p.y)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        Rect::new(min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_close(a: Position, b: Position) {
+        assert!(
+            (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn transform_points() {
+        let point = Position::new(10.0, 20.0);
+
+        assert_eq!(
+            Transform::translate(5.0, -5.0).transform_point(point),
+            Position::new(15.0, 15.0)
+        );
+        assert_eq!(
+            Transform::scale(2.0, 3.0).transform_point(point),
+            Position::new(20.0, 60.0)
+        );
+        // Clockwise on screen, so the x axis turns towards the y axis
+        assert_close(
+            Transform::rotate(FRAC_PI_2).transform_point(Position::new(1.0, 0.0)),
+            Position::new(0.0, 1.0),
+        );
+        assert_close(
+            Transform::skew(FRAC_PI_2 / 2.0, 0.0).transform_point(point),
+            Position::new(30.0, 20.0),
+        );
+    }
+
+    #[test]
+    fn compose_in_order() {
+        let transform = Transform::scale(2.0, 2.0).then(Transform::translate(10.0, 0.0));
+        assert_eq!(
+            transform.transform_point(Position::new(1.0, 1.0)),
+            Position::new(12.0, 2.0)
+        );
+
+        let transform = Transform::translate(10.0, 0.0).then(Transform::scale(2.0, 2.0));
+        assert_eq!(
+            transform.transform_point(Position::new(1.0, 1.0)),
+            Position::new(22.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn rotate_around_point() {
+        let transform = Transform::rotate(FRAC_PI_2).around(Position::new(10.0, 10.0));
+
+        assert_close(
+            transform.transform_point(Position::new(10.0, 10.0)),
+            Position::new(10.0, 10.0),
+        );
+        assert_close(
+            transform.transform_point(Position::new(20.0, 10.0)),
+            Position::new(10.0, 20.0),
+        );
+    }
+
+    #[test]
+    fn invert_undoes_transform() {
+        let transform = Transform::rotate(0.3)
+            .then(Transform::skew(0.2, 0.1))
+            .then(Transform::scale(2.0, 0.5))
+            .then(Transform::translate(40.0, -7.0));
+        let inverse = transform.invert().unwrap();
+
+        let point = Position::new(13.0, -4.0);
+        assert_close(
+            inverse.transform_point(transform.transform_point(point)),
+            point,
+        );
+
+        let identity = transform.then(inverse).to_array();
+        for (a, b) in identity.iter().zip(Transform::IDENTITY.to_array()) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn singular_transform_has_no_inverse() {
+        assert_eq!(Transform::scale(0.0, 1.0).invert(), None);
+        assert_eq!(
+            Transform::new([1.0, 2.0, 2.0, 4.0, 0.0, 0.0]).invert(),
+            None
+        );
+        assert_eq!(Transform::scale(1e-30, 1e-30).invert(), None);
+    }
+
+    #[test]
+    fn invert_strong_zoom_out() {
+        let transform = Transform::scale(1e-4, 1e-4)
+            .then(Transform::rotate(0.5))
+            .then(Transform::translate(300.0, 200.0));
+        let inverse = transform.invert().unwrap();
+
+        let point = Position::new(1200.0, -800.0);
+        let round_trip = inverse.transform_point(transform.transform_point(point));
+        assert!((round_trip.x - point.x).abs() < 0.5);
+        assert!((round_trip.y - point.y).abs() < 0.5);
+    }
+
+    #[test]
+    fn bounds_of_rotated_rect() {
+        let transform = Transform::rotate(FRAC_PI_2);
+        let bounds = transform.transform_rect(Rect::new(0.0, 0.0, 20.0, 10.0));
+
+        assert!((bounds.x() + 10.0).abs() < 1e-4);
+        assert!(bounds.y().abs() < 1e-4);
+        assert!((bounds.width() - 10.0).abs() < 1e-4);
+        assert!((bounds.height() - 20.0).abs() < 1e-4);
+        assert!(!transform.is_axis_aligned());
+        assert!(Transform::scale(2.0, 1.0).is_axis_aligned());
+    }
+}
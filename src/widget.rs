@@ -0,0 +1,321 @@
+use crate::{
+    BoxSizing, Constraints, CornerRadii, DrawContext, Fonts, LayoutContext, Position, Rect, Size,
+    Transform,
+};
+
+/// The building block of a user interface.
+///
+/// Widgets describe their children in [`Widget::build`], size and
+/// position them in [`Widget::layout`] and emit their own geometry in
+/// [`Widget::draw`]. Children are drawn on top of their parent.
+pub trait Widget {
+    /// Creates the children of this widget, called once when the
+    /// widget is added to the tree.
+    ///
+    /// Leaf widgets have no children, which is the default.
+    fn build(&self) -> Vec<Box<dyn Widget>> {
+        Vec::new()
+    }
+
+    /// How this widget wants to be sized, which fits its
+    /// content by default.
+    fn sizing(&self) -> BoxSizing {
+        BoxSizing::default()
+    }
+
+    /// Computes the size of this widget within `constraints`, laying out
+    /// and positioning any children through `ctx`.
+    ///
+    /// The constraints have already been narrowed by [`Widget::sizing`],
+    /// and the returned size is clamped to them.
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size;
+
+    /// Draws this widget within [`DrawContext::bounds`].
+    fn draw(&self, ctx: &mut DrawContext);
+
+    /// The transform applied when drawing and hit testing this widget
+    /// and its children, relative to the top left corner of the widget.
+    ///
+    /// Transforms don't affect layout.
+    fn transform(&self) -> Transform {
+        Transform::IDENTITY
+    }
+
+    /// The corner radii to clip children to the bounds of this widget
+    /// with, or `None` to let children draw outside of it.
+    fn clip(&self) -> Option<CornerRadii> {
+        None
+    }
+}
+
+/// A widget in the widget tree, along with its children and
+/// the result of its last layout.
+pub struct WidgetNode {
+    widget: Box<dyn Widget>,
+    children: Vec<WidgetNode>,
+    /// The position relative to the parent.
+    position: Position,
+    size: Size,
+}
+
+impl WidgetNode {
+    /// Creates a node for `widget`, recursively building its children.
+    pub fn new(widget: impl Widget + 'static) -> Self {
+        Self::from_boxed(Box::new(widget))
+    }
+
+    /// Creates a node for a boxed `widget`, recursively building
+    /// its children.
+    pub fn from_boxed(widget: Box<dyn Widget>) -> Self {
+        let children = widget.build().into_iter().map(Self::from_boxed).collect();
+
+        Self {
+            widget,
+            children,
+            position: Position::ZERO,
+            size: Size::ZERO,
+        }
+    }
+
+    /// The child nodes of this node.
+    pub fn children(&self) -> &[WidgetNode] {
+        &self.children
+    }
+
+    /// The size computed in the last layout.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// The position relative to the parent.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub(crate) fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+
+    /// The sizing of the widget.
+    pub fn sizing(&self) -> BoxSizing {
+        self.widget.sizing()
+    }
+
+    /// Lays out this node and its children within `constraints`,
+    /// measuring text with `fonts`.
+    pub fn layout(&mut self, constraints: Constraints, fonts: &Fonts) -> Size {
+        let constraints = self.widget.sizing().constrain(constraints);
+        let mut ctx = LayoutContext::new(&mut self.children, fonts);
+        let size = self.widget.layout(constraints, &mut ctx);
+        self.size = constraints.constrain(size);
+        self.size
+    }
+
+    /// Draws this node and then its children, with `origin` being the
+    /// absolute position of the parent.
+    pub fn draw(&self, origin: Position, ctx: &mut DrawContext) {
+        let position = origin.translate(self.position.x, self.position.y);
+        ctx.push_transform(self.widget.transform().around(position));
+        let bounds = Rect::from_parts(position, self.size);
+        ctx.set_bounds(bounds);
+        self.widget.draw(ctx);
+
+        let clip = self.widget.clip();
+        if let Some(radii) = clip {
+            ctx.push_rounded_clip(bounds, radii);
+        }
+        for child in &self.children {
+            child.draw(position, ctx);
+        }
+        if clip.is_some() {
+            ctx.pop_clip();
+        }
+        ctx.pop_transform();
+    }
+
+    /// Finds the deepest node under `point`, which is relative to the
+    /// parent of this node, using the inverse of each widget's transform.
+    ///
+    /// Returns the indices of the children leading to the node, which is
+    /// empty if it's this node, or `None` if the point misses this node.
+    pub fn hit_test(&self, point: Position) -> Option<Vec<usize>> {
+        let point = point.translate(-self.position.x, -self.position.y);
+        let local = self.widget.transform().invert()?.transform_point(point);
+
+        let bounds = Rect::from_parts(Position::ZERO, self.size);
+        if !bounds.contains(local) {
+            return None;
+        }
+
+        // Later children are drawn on top, so they're hit first
+        for (i, child) in self.children.iter().enumerate().rev() {
+            if let Some(mut path) = child.hit_test(local) {
+                path.insert(0, i);
+                return Some(path);
+            }
+        }
+
+        Some(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+    use crate::text::tests::fonts;
+
+    struct Square(f32);
+
+    impl Widget for Square {
+        fn layout(&mut self, _: Constraints, _: &mut LayoutContext) -> Size {
+            Size::splat(self.0)
+        }
+
+        fn draw(&self, ctx: &mut DrawContext) {
+            let bounds = ctx.bounds();
+            ctx.quad(bounds, Color::BLACK);
+        }
+    }
+
+    /// Stacks its children vertically with 10px of padding.
+    struct Padded;
+
+    impl Widget for Padded {
+        fn build(&self) -> Vec<Box<dyn Widget>> {
+            vec![Box::new(Square(20.0)), Box::new(Square(30.0))]
+        }
+
+        fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+            let mut y = 10.0;
+            let mut width: f32 = 0.0;
+            for i in 0..ctx.len() {
+                let size = ctx.layout_child(i, Constraints::loose(constraints.max));
+                ctx.position_child(i, Position::new(10.0, y));
+                y += size.height;
+                width = width.max(size.width);
+            }
+            Size::new(width + 20.0, y + 10.0)
+        }
+
+        fn draw(&self, _: &mut DrawContext) {}
+    }
+
+    /// Draws its child at twice the size.
+    struct Zoomed;
+
+    impl Widget for Zoomed {
+        fn build(&self) -> Vec<Box<dyn Widget>> {
+            vec![Box::new(Padded)]
+        }
+
+        fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+            ctx.layout_child(0, constraints)
+        }
+
+        fn draw(&self, _: &mut DrawContext) {}
+
+        fn transform(&self) -> Transform {
+            Transform::scale(2.0, 2.0)
+        }
+    }
+
+    /// Clips a child that's larger than itself.
+    struct Overflow;
+
+    impl Widget for Overflow {
+        fn build(&self) -> Vec<Box<dyn Widget>> {
+            vec![Box::new(Square(100.0))]
+        }
+
+        fn layout(&mut self, _: Constraints, ctx: &mut LayoutContext) -> Size {
+            ctx.layout_child(0, Constraints::unbounded());
+            Size::splat(40.0)
+        }
+
+        fn draw(&self, _: &mut DrawContext) {}
+
+        fn clip(&self) -> Option<CornerRadii> {
+            Some(CornerRadii::all(0.0))
+        }
+    }
+
+    #[test]
+    fn build_children() {
+        let node = WidgetNode::new(Padded);
+        assert_eq!(node.children().len(), 2);
+    }
+
+    #[test]
+    fn layout_children() {
+        let mut node = WidgetNode::new(Padded);
+        let size = node.layout(Constraints::unbounded(), &fonts());
+
+        assert_eq!(size, Size::new(50.0, 70.0));
+        assert_eq!(node.children()[0].position(), Position::new(10.0, 10.0));
+        assert_eq!(node.children()[1].position(), Position::new(10.0, 30.0));
+        assert_eq!(node.children()[1].size(), Size::splat(30.0));
+    }
+
+    #[test]
+    fn constrain_layout_size() {
+        let mut node = WidgetNode::new(Square(100.0));
+        let size = node.layout(Constraints::loose(Size::new(50.0, 200.0)), &fonts());
+        assert_eq!(size, Size::new(50.0, 100.0));
+    }
+
+    #[test]
+    fn draw_children_at_absolute_positions() {
+        let mut node = WidgetNode::new(Padded);
+        node.layout(Constraints::unbounded(), &fonts());
+
+        let mut ctx = DrawContext::new();
+        node.draw(Position::new(5.0, 5.0), &mut ctx);
+
+        let vertices = ctx.vertices();
+        assert_eq!(vertices.len(), 8);
+        assert_eq!(vertices[0].position, [15.0, 15.0]);
+        assert_eq!(vertices[4].position, [15.0, 35.0]);
+    }
+
+    #[test]
+    fn draw_with_transform() {
+        let mut node = WidgetNode::new(Zoomed);
+        node.layout(Constraints::unbounded(), &fonts());
+
+        let mut ctx = DrawContext::new();
+        node.draw(Position::new(5.0, 5.0), &mut ctx);
+
+        let vertices = ctx.vertices();
+        assert_eq!(vertices[0].position, [25.0, 25.0]);
+        assert_eq!(vertices[3].position, [65.0, 65.0]);
+    }
+
+    #[test]
+    fn hit_test_through_transform() {
+        let mut node = WidgetNode::new(Zoomed);
+        node.layout(Constraints::unbounded(), &fonts());
+
+        assert_eq!(node.hit_test(Position::new(30.0, 30.0)), Some(vec![0, 0]));
+        assert_eq!(node.hit_test(Position::new(90.0, 130.0)), Some(vec![0]));
+        // Outside the untransformed bounds, but inside the drawn ones
+        assert_eq!(node.hit_test(Position::new(60.0, 5.0)), Some(vec![0]));
+        assert_eq!(node.hit_test(Position::new(120.0, 10.0)), None);
+    }
+
+    #[test]
+    fn clip_children() {
+        let mut node = WidgetNode::new(Overflow);
+        node.layout(Constraints::unbounded(), &fonts());
+
+        let mut ctx = DrawContext::new();
+        node.draw(Position::new(5.0, 5.0), &mut ctx);
+
+        let list = ctx.take_list();
+        assert_eq!(
+            list.batches[0].scissor,
+            Some(Rect::new(5.0, 5.0, 40.0, 40.0))
+        );
+        assert_eq!(ctx.clip_bounds(), None);
+    }
+}